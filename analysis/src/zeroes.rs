@@ -8,34 +8,36 @@ extern crate xorf;
 
 use core::convert::TryFrom;
 use rand::Rng;
+use std::collections::VecDeque;
 use xorf::*;
 
 const SIZE: usize = 1_000_000;
 
-macro_rules! print_zeroes {
-    ($filter:ident) => {{
-        let mut keys: Vec<u64> = Vec::with_capacity(SIZE);
+fn keys() -> Vec<u64> {
+    let mut keys: Vec<u64> = Vec::with_capacity(SIZE);
 
-        for _ in 0..SIZE {
-            let key: u64 = rand::thread_rng().gen();
-            keys.push(key);
+    for _ in 0..SIZE {
+        let key: u64 = rand::thread_rng().gen();
+        keys.push(key);
+    }
+    keys
+}
+
+fn print_zeroes(slots: impl Iterator<Item = (usize, u64)>) {
+    let window_size = 2000;
+    // Indices of the zero fingerprints in the window ending at the current slot.
+    let mut zeroes: VecDeque<usize> = VecDeque::new();
+    for (i, t) in slots {
+        if t == 0 {
+            zeroes.push_back(i);
+        }
+        while zeroes.front().map_or(false, |&z| z + window_size <= i) {
+            zeroes.pop_front();
         }
-        let filter = $filter::try_from(keys).unwrap();
-        let fp = filter.fingerprints;
-        let window_size = 2000;
-        let mut zeroes: usize = 0;
-        for (i, t) in fp.iter().enumerate() {
-            if *t == 0 {
-                zeroes += 1;
-            }
-            if i > window_size && fp[i - window_size] == 0 {
-                zeroes -= 1;
-            }
-            if i > window_size && i % 333 == 0 {
-                println!("{}", zeroes);
-            }
+        if i > window_size && i % 333 == 0 {
+            println!("{}", zeroes.len());
         }
-    }};
+    }
 }
 
 fn main() {
@@ -45,16 +47,12 @@ fn main() {
         .expect("Expected filter argument");
 
     match filter_str.as_ref() {
-        "BinaryFuse8" => print_zeroes!(BinaryFuse8),
-        "BinaryFuse16" => print_zeroes!(BinaryFuse16),
-        "Fuse8" => {
-            print_zeroes!(Fuse8)
-        }
-        "Fuse16" => {
-            print_zeroes!(Fuse16)
-        }
-        "Xor8" => print_zeroes!(Xor8),
-        "Xor16" => print_zeroes!(Xor16),
+        "BinaryFuse8" => print_zeroes(BinaryFuse8::try_from(keys()).unwrap().slots()),
+        "BinaryFuse16" => print_zeroes(BinaryFuse16::try_from(keys()).unwrap().slots()),
+        "Fuse8" => print_zeroes(Fuse8::try_from(keys()).unwrap().slots()),
+        "Fuse16" => print_zeroes(Fuse16::try_from(keys()).unwrap().slots()),
+        "Xor8" => print_zeroes(Xor8::from(keys()).slots()),
+        "Xor16" => print_zeroes(Xor16::from(keys()).slots()),
         _ => panic!("Filter {} is invalid", filter_str),
     };
 }
@@ -36,5 +36,51 @@ fn contains(c: &mut Criterion) {
     });
 }
 
-criterion_group!(bfuse8, from, contains);
+fn contains_many(c: &mut Criterion) {
+    const BATCH_SIZE: usize = 10_000;
+    let mut group = c.benchmark_group("BinaryFuse8");
+
+    let mut rng = rand::thread_rng();
+    let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+    let filter = BinaryFuse8::try_from(&keys).unwrap();
+    let queries: Vec<u64> = (0..BATCH_SIZE).map(|_| rng.gen()).collect();
+    let mut out = vec![false; BATCH_SIZE];
+
+    group.bench_function(BenchmarkId::new("contains_loop", BATCH_SIZE), |b| {
+        b.iter(|| {
+            for (key, out) in queries.iter().zip(out.iter_mut()) {
+                *out = filter.contains(key);
+            }
+        });
+    });
+    group.bench_function(BenchmarkId::new("contains_many", BATCH_SIZE), |b| {
+        b.iter(|| filter.contains_many(&queries, &mut out));
+    });
+}
+
+fn contains_many_large(c: &mut Criterion) {
+    // Large enough that the filter does not fit in cache.
+    const LARGE_SAMPLE_SIZE: usize = 10_000_000;
+    const BATCH_SIZE: usize = 100_000;
+    let mut group = c.benchmark_group("BinaryFuse8");
+
+    let mut rng = rand::thread_rng();
+    let keys: Vec<u64> = (0..LARGE_SAMPLE_SIZE).map(|_| rng.gen()).collect();
+    let filter = BinaryFuse8::try_from(&keys).unwrap();
+    let queries: Vec<u64> = (0..BATCH_SIZE).map(|_| rng.gen()).collect();
+    let mut out = vec![false; BATCH_SIZE];
+
+    group.bench_function(BenchmarkId::new("contains_loop", LARGE_SAMPLE_SIZE), |b| {
+        b.iter(|| {
+            for (key, out) in queries.iter().zip(out.iter_mut()) {
+                *out = filter.contains(key);
+            }
+        });
+    });
+    group.bench_function(BenchmarkId::new("contains_many", LARGE_SAMPLE_SIZE), |b| {
+        b.iter(|| filter.contains_many(&queries, &mut out));
+    });
+}
+
+criterion_group!(bfuse8, from, contains, contains_many, contains_many_large);
 criterion_main!(bfuse8);
@@ -0,0 +1,83 @@
+#![allow(deprecated)] // Fuse filters are deprecated, but are compared against here.
+
+#[macro_use]
+extern crate criterion;
+extern crate core;
+extern crate rand;
+extern crate xorf;
+
+use core::convert::TryFrom;
+use criterion::{BenchmarkId, Criterion, Throughput};
+use rand::Rng;
+use xorf::{BinaryFuse8, Filter, Fuse8, Xor8};
+
+const SAMPLE_SIZE: u32 = 500_000;
+const BATCH_SIZE: usize = 10_000;
+
+/// Compares the construction time, lookup throughput, and size of the 8-bit filter families over
+/// the same keys.
+fn compare(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Compare8");
+
+    let mut rng = rand::thread_rng();
+    let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+    let queries: Vec<u64> = (0..BATCH_SIZE).map(|_| rng.gen()).collect();
+
+    let xor = Xor8::from(&keys);
+    let fuse = Fuse8::try_from(&keys).unwrap();
+    let bfuse = BinaryFuse8::try_from(&keys).unwrap();
+
+    // Criterion only reports times, so sizes are printed alongside them.
+    for (name, len) in [
+        ("Xor8", xor.len()),
+        ("Fuse8", fuse.len()),
+        ("BinaryFuse8", bfuse.len()),
+    ] {
+        println!(
+            "{}: {:.2} bits/entry over {} keys",
+            name,
+            (len * 8) as f64 / SAMPLE_SIZE as f64,
+            SAMPLE_SIZE
+        );
+    }
+
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(SAMPLE_SIZE.into()));
+    group.bench_with_input(
+        BenchmarkId::new("Xor8/from", SAMPLE_SIZE),
+        &keys,
+        |b, keys| {
+            b.iter(|| Xor8::from(keys));
+        },
+    );
+    group.bench_with_input(
+        BenchmarkId::new("Fuse8/from", SAMPLE_SIZE),
+        &keys,
+        |b, keys| {
+            b.iter(|| Fuse8::try_from(keys).unwrap());
+        },
+    );
+    group.bench_with_input(
+        BenchmarkId::new("BinaryFuse8/from", SAMPLE_SIZE),
+        &keys,
+        |b, keys| {
+            b.iter(|| BinaryFuse8::try_from(keys).unwrap());
+        },
+    );
+
+    group.sample_size(100);
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+    macro_rules! bench_contains {
+        ($name:literal, $filter:expr) => {
+            group.bench_function(BenchmarkId::new($name, BATCH_SIZE), |b| {
+                b.iter(|| queries.iter().filter(|key| $filter.contains(key)).count());
+            });
+        };
+    }
+    bench_contains!("Xor8/contains", xor);
+    bench_contains!("Fuse8/contains", fuse);
+    bench_contains!("BinaryFuse8/contains", bfuse);
+}
+
+criterion_group!(compare8, compare);
+criterion_main!(compare8);
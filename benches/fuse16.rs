@@ -1,3 +1,5 @@
+#![allow(deprecated)] // Fuse filters are deprecated
+
 #[macro_use]
 extern crate criterion;
 extern crate core;
@@ -5,7 +5,7 @@ extern crate xorf;
 
 use criterion::{BenchmarkId, Criterion};
 use rand::Rng;
-use xorf::{Filter, Xor8};
+use xorf::{hashing, Filter, Xor8};
 
 const SAMPLE_SIZE: u32 = 500_000;
 
@@ -34,5 +34,19 @@ fn contains(c: &mut Criterion) {
     });
 }
 
-criterion_group!(xor8, from, contains);
+fn indices(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Xor8");
+
+    let mut rng = rand::thread_rng();
+    let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+    let filter = Xor8::from(&keys);
+
+    // Isolates the index computation from the fingerprint loads of `contains`.
+    group.bench_function(BenchmarkId::new("indices", SAMPLE_SIZE), |b| {
+        let hash = hashing::mix(rng.gen(), filter.seed);
+        b.iter(|| hashing::xor_indices(criterion::black_box(hash), filter.block_length));
+    });
+}
+
+criterion_group!(xor8, from, contains, indices);
 criterion_main!(xor8);
@@ -0,0 +1,40 @@
+//! Builds a `BinaryFuse8` filter, writes it to a file, and queries the filter read back from the
+//! file.
+//!
+//! Run with `cargo run --example persist_filter`.
+
+use std::{convert::TryFrom, fs};
+use xorf::{BinaryFuse8, Filter};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let keys: Vec<u64> = (0..1_000_000).map(|key| key * 3).collect();
+    let filter = BinaryFuse8::try_from(&keys)?;
+
+    // `to_bytes` writes a self-describing, little-endian layout that `from_bytes` validates when
+    // reading, so the file can be moved between machines and corrupt files are reported as errors.
+    let path = std::env::temp_dir().join("xorf-persist-filter.bin");
+    fs::write(&path, filter.to_bytes())?;
+    println!(
+        "Wrote a filter of {} keys to {} ({} bytes)",
+        keys.len(),
+        path.display(),
+        fs::metadata(&path)?.len()
+    );
+
+    let bytes = fs::read(&path)?;
+    let reloaded = BinaryFuse8::from_bytes(&bytes)?;
+    fs::remove_file(&path)?;
+
+    assert_eq!(filter, reloaded);
+    assert!(keys.iter().all(|key| reloaded.contains(key)));
+    let false_positives = (0..1_000_000)
+        .map(|key| key * 3 + 1)
+        .filter(|key| reloaded.contains(key))
+        .count();
+    println!(
+        "Reloaded filter contains every key, with {} false positives in 1000000 other keys",
+        false_positives
+    );
+
+    Ok(())
+}
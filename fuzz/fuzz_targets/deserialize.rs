@@ -0,0 +1,65 @@
+//! Deserializes filters from arbitrary bytes with `bincode` and `serde`, and looks up arbitrary
+//! keys in the ones that validate. Neither may panic.
+
+#![no_main]
+#![allow(deprecated)]
+
+use bincode::config;
+use libfuzzer_sys::fuzz_target;
+use xorf::{
+    BinaryFuse16, BinaryFuse24, BinaryFuse32, BinaryFuse64, BinaryFuse8, Filter, Fuse16, Fuse32,
+    Fuse8, HashProxy, Murmur3Hasher, ShardedFilter, Xor16, Xor32, Xor64, Xor8,
+};
+
+// Untrusted input must be decoded with a limit, or bincode allocates whatever length the input
+// claims a fingerprint vector has.
+const LIMIT: usize = 1 << 20;
+
+macro_rules! decode {
+    ($filter:ty, $bytes:expr) => {{
+        let decoded: Result<($filter, usize), _> =
+            bincode::decode_from_slice($bytes, config::standard().with_limit::<LIMIT>());
+        let deserialized: Result<($filter, usize), _> =
+            bincode::serde::decode_from_slice($bytes, config::standard().with_limit::<LIMIT>());
+        decoded
+            .into_iter()
+            .chain(deserialized)
+            .map(|(filter, _)| filter)
+    }};
+}
+
+macro_rules! check {
+    ($filter:ty, $bytes:expr, $keys:expr) => {
+        for filter in decode!($filter, $bytes) {
+            // Deserialized filters are only safe to query once validated.
+            if filter.validate().is_ok() {
+                for key in $keys {
+                    filter.contains(key);
+                }
+            }
+        }
+    };
+    // Filters that can't be validated are only deserialized.
+    ($filter:ty, $bytes:expr) => {
+        decode!($filter, $bytes).for_each(drop);
+    };
+}
+
+fuzz_target!(|input: (Vec<u8>, Vec<u64>)| {
+    let (bytes, keys) = input;
+    check!(BinaryFuse8, &bytes, &keys);
+    check!(BinaryFuse16, &bytes, &keys);
+    check!(BinaryFuse24, &bytes, &keys);
+    check!(BinaryFuse32, &bytes, &keys);
+    check!(BinaryFuse64, &bytes, &keys);
+    check!(Xor8, &bytes, &keys);
+    check!(Xor16, &bytes, &keys);
+    check!(Xor32, &bytes, &keys);
+    check!(Xor64, &bytes, &keys);
+
+    check!(Fuse8, &bytes);
+    check!(Fuse16, &bytes);
+    check!(Fuse32, &bytes);
+    check!(ShardedFilter<BinaryFuse8>, &bytes);
+    check!(HashProxy<u64, Murmur3Hasher, Xor8>, &bytes);
+});
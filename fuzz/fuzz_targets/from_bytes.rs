@@ -0,0 +1,45 @@
+//! Reads filters from arbitrary bytes with `from_bytes`, `read_from`, and the C and Go layouts,
+//! and looks up arbitrary keys in the ones that are read. Neither may panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use xorf::{
+    BinaryFuse16, BinaryFuse24, BinaryFuse32, BinaryFuse64, BinaryFuse8, Filter, Xor16, Xor32,
+    Xor64, Xor8,
+};
+
+macro_rules! check {
+    ($read:expr, $keys:expr) => {
+        if let Ok(filter) = $read {
+            // Filters read from bytes are documented to be valid.
+            assert!(filter.validate().is_ok());
+            for key in $keys {
+                filter.contains(key);
+            }
+        }
+    };
+    ($filter:ty, $bytes:expr, $keys:expr) => {
+        check!(<$filter>::from_bytes($bytes), $keys);
+        check!(<$filter>::read_from(&mut &$bytes[..]), $keys);
+    };
+}
+
+fuzz_target!(|input: (Vec<u8>, Vec<u64>)| {
+    let (bytes, keys) = input;
+    check!(BinaryFuse8, &bytes, &keys);
+    check!(BinaryFuse16, &bytes, &keys);
+    check!(BinaryFuse24, &bytes, &keys);
+    check!(BinaryFuse32, &bytes, &keys);
+    check!(BinaryFuse64, &bytes, &keys);
+    check!(Xor8, &bytes, &keys);
+    check!(Xor16, &bytes, &keys);
+    check!(Xor32, &bytes, &keys);
+    check!(Xor64, &bytes, &keys);
+
+    check!(BinaryFuse8::from_go_bytes(&bytes), &keys);
+    check!(Xor8::from_c_layout(&bytes), &keys);
+    check!(Xor16::from_c_layout(&bytes), &keys);
+    check!(Xor32::from_c_layout(&bytes), &keys);
+    check!(Xor64::from_c_layout(&bytes), &keys);
+});
@@ -0,0 +1,836 @@
+//! Implements Binary Fuse filters as described in [Binary Fuse Filters: Fast and Smaller Than Xor Filters].
+//!
+//! [Binary Fuse Filters: Fast and Smaller Than Xor Filters]: https://arxiv.org/abs/2201.01174
+
+use crate::{
+    bfuse_contains_impl, bfuse_contains_many_impl, bfuse_from_impl, dedup_sorted,
+    prelude::{
+        all_distinct,
+        bfuse::{dimensions, hash_of_hash, size_factor, valid_params, Scratch, MAX_SEGMENT_LENGTH},
+        bytes::{header, key_count_bytes, read_key_count, write_fingerprints, Reader, BINARY_FUSE},
+        fmt_summary, mix, FingerprintsPreview,
+    },
+    BuildError, FillRng, Filter, Fingerprint, Probe,
+};
+use alloc::{boxed::Box, vec::Vec};
+use core::{convert::TryFrom, fmt};
+
+#[cfg(feature = "std")]
+use crate::prelude::bytes::{invalid_data, write_fingerprints_to, StreamReader};
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+#[cfg(feature = "rayon")]
+use rayon::iter::IndexedParallelIterator;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bincode")]
+use bincode::{Decode, Encode};
+
+/// Binary Fuse filter using fingerprints of type `F`: an Xor-like filter with fingerprints
+/// arranged in a binary-partitioned [fuse graph].
+///
+/// A `BinaryFuse` filter uses around `1.125 * F::BITS` bits per entry of the set it is constructed
+/// from, and has a false positive rate of around `2^-F::BITS`. Prefer using one of the
+/// [`BinaryFuse8`], [`BinaryFuse16`], [`BinaryFuse24`], [`BinaryFuse32`], or [`BinaryFuse64`]
+/// aliases.
+///
+/// A `BinaryFuse` is constructed from a set of 64-bit unsigned integers and is immutable.
+/// Construction may fail, but usually only if there are duplicate keys.
+///
+/// Since construction may fail, `BinaryFuse` does not implement [`FromIterator`]. To construct a
+/// filter from an iterator pipeline, pass the iterator to [`try_from_iterator`], or collect it
+/// into a `Vec<u64>` first if it can't be cloned.
+///
+/// The default `BinaryFuse` is empty, the same as a filter constructed from no keys.
+///
+/// Serializing and deserializing `BinaryFuse` filters can be enabled with the [`serde`] feature (or [`bincode`] for bincode).
+///
+/// [fuse graph]: https://arxiv.org/abs/1907.04749
+/// [`BinaryFuse8`]: crate::BinaryFuse8
+/// [`BinaryFuse16`]: crate::BinaryFuse16
+/// [`BinaryFuse24`]: crate::BinaryFuse24
+/// [`BinaryFuse32`]: crate::BinaryFuse32
+/// [`BinaryFuse64`]: crate::BinaryFuse64
+/// [`try_from_iterator`]: Self::try_from_iterator
+/// [`serde`]: http://serde.rs
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct BinaryFuse<F> {
+    pub(crate) seed: u64,
+    pub(crate) segment_length: u32,
+    pub(crate) segment_length_mask: u32,
+    pub(crate) segment_count_length: u32,
+    // Only recorded by `to_bytes`, so that the serde and bincode layouts stay the same as those
+    // of earlier versions. Filters deserialized from those layouts, or from bytes written before
+    // this was recorded, are assumed to have zeroed empty slots.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) empty_slots_randomized: bool,
+    // Only recorded by `to_bytes`, like `empty_slots_randomized`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) key_count: Option<usize>,
+    /// The fingerprints for the filter
+    pub fingerprints: Box<[F]>,
+}
+
+// Encoded by hand, rather than derived, to leave out the fields only recorded by `to_bytes`.
+#[cfg(feature = "bincode")]
+impl<F: Encode> Encode for BinaryFuse<F> {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        self.seed.encode(encoder)?;
+        self.segment_length.encode(encoder)?;
+        self.segment_length_mask.encode(encoder)?;
+        self.segment_count_length.encode(encoder)?;
+        self.fingerprints.encode(encoder)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<F: Decode<Context> + 'static, Context> Decode<Context> for BinaryFuse<F> {
+    fn decode<D: bincode::de::Decoder<Context = Context>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self {
+            seed: Decode::decode(decoder)?,
+            segment_length: Decode::decode(decoder)?,
+            segment_length_mask: Decode::decode(decoder)?,
+            segment_count_length: Decode::decode(decoder)?,
+            empty_slots_randomized: false,
+            key_count: None,
+            fingerprints: Decode::decode(decoder)?,
+        })
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<'de, F: bincode::BorrowDecode<'de, Context> + 'de, Context> bincode::BorrowDecode<'de, Context>
+    for BinaryFuse<F>
+{
+    fn borrow_decode<D: bincode::de::BorrowDecoder<'de, Context = Context>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self {
+            seed: Decode::decode(decoder)?,
+            segment_length: Decode::decode(decoder)?,
+            segment_length_mask: Decode::decode(decoder)?,
+            segment_count_length: Decode::decode(decoder)?,
+            empty_slots_randomized: false,
+            key_count: None,
+            fingerprints: bincode::BorrowDecode::borrow_decode(decoder)?,
+        })
+    }
+}
+
+/// Statistics about the construction of a [`BinaryFuse`] filter, returned by
+/// [`BinaryFuse::try_from_iterator_with_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BuildStats {
+    /// The number of filter seeds tried, including the one the filter was constructed with.
+    pub attempts: usize,
+    /// The number of keys found to be duplicates of other keys, which are only placed once.
+    pub duplicates: usize,
+    /// The seed the filter was constructed with.
+    pub final_seed: u64,
+}
+
+/// The parameters of a [`BinaryFuse`] filter, returned by [`BinaryFuse::descriptor`], with a C
+/// layout so that it can be passed by value across FFI.
+///
+/// A C consumer can declare the matching struct
+///
+/// ```c
+/// struct descriptor {
+///     uint64_t seed;
+///     uint32_t segment_length;
+///     uint32_t segment_length_mask;
+///     uint32_t segment_count_length;
+/// };
+/// ```
+///
+/// whose fields are in the same order, with the same types, as this struct's.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Descriptor {
+    /// The seed keys are mixed with before hashing them into the filter.
+    pub seed: u64,
+    /// The number of fingerprints in each segment of the filter.
+    pub segment_length: u32,
+    /// The mask applied to hashes to index within a segment, `segment_length - 1`.
+    pub segment_length_mask: u32,
+    /// The number of fingerprints in the segments a key's first index can fall in.
+    pub segment_count_length: u32,
+}
+
+impl<F: Fingerprint> Filter<u64> for BinaryFuse<F> {
+    /// Returns `true` if the filter contains the specified key.
+    /// Has a false positive rate of around `2^-F::BITS`.
+    /// Has no false negatives.
+    #[inline]
+    fn contains(&self, key: &u64) -> bool {
+        bfuse_contains_impl!(*key, self, fingerprint F)
+    }
+
+    /// Looks up `keys` in batches, prefetching the fingerprints of each batch before reading
+    /// them. Faster than calling [`contains`] on each key for filters that don't fit in cache; in
+    /// the `contains_many` benchmark over a 10M-entry `BinaryFuse8`, lookups take about a third
+    /// less time.
+    ///
+    /// [`contains`]: Self::contains
+    fn contains_many(&self, keys: &[u64], out: &mut [bool]) {
+        bfuse_contains_many_impl!(keys, out, self, fingerprint F)
+    }
+
+    fn len(&self) -> usize {
+        self.fingerprints.len()
+    }
+}
+
+impl<F: Fingerprint> BinaryFuse<F> {
+    /// Try to construct the filter from a key iterator. Can be used directly
+    /// if you don't have a contiguous array of u64 keys.
+    ///
+    /// Note: the iterator will be iterated over multiple times while building
+    /// the filter. If using a hash function to map the key, it may be cheaper
+    /// just to create a scratch array of hashed keys that you pass in.
+    pub fn try_from_iterator<T>(keys: T) -> Result<Self, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        bfuse_from_impl!(keys fingerprint F, max iter 1_000)
+    }
+
+    /// Try to construct the filter from a key iterator as [`try_from_iterator`] does, calling
+    /// `on_attempt` with the number of each construction attempt as it starts.
+    ///
+    /// Each attempt tries a new filter seed and takes time linear in the number of keys. A first
+    /// attempt usually succeeds, so a growing number of attempts points to duplicate keys or
+    /// other pathological input, which can be reported before construction gives up.
+    ///
+    /// [`try_from_iterator`]: Self::try_from_iterator
+    pub fn try_from_iterator_with_progress<T>(
+        keys: T,
+        mut on_attempt: impl FnMut(usize),
+    ) -> Result<Self, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        bfuse_from_impl!(keys fingerprint F, max iter 1_000, rng seed 1, on attempt on_attempt)
+    }
+
+    /// Try to construct the filter from a key iterator yielding keys in strictly ascending order,
+    /// such as keys that were sorted and deduplicated upstream.
+    ///
+    /// Constructs the same filter as [`try_from_iterator`]. In debug builds, the keys are checked
+    /// to be in order in a single pass, rather than collected into a set to check that they are
+    /// distinct, which dominates debug builds of large filters.
+    ///
+    /// [`try_from_iterator`]: Self::try_from_iterator
+    pub fn try_from_sorted_iterator<T>(keys: T) -> Result<Self, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        #[cfg(debug_assertions)]
+        {
+            use crate::prelude::strictly_ascending;
+            debug_assert!(
+                strictly_ascending(keys.clone()),
+                "Keys must be sorted in ascending order without duplicates."
+            );
+        }
+        bfuse_from_impl!(keys fingerprint F, max iter 1_000, rng seed 1, distinct)
+    }
+
+    /// Try to construct the filter from keys that can only be iterated over once, such as keys
+    /// read from a stream. Unlike [`try_from_iterator`], the keys need not be [`Clone`] or
+    /// [`ExactSizeIterator`].
+    ///
+    /// Construction iterates over the keys multiple times, so they are first collected into a
+    /// `Vec`, which allocates `8 * n` bytes for `n` keys on top of the filter itself. Prefer
+    /// [`try_from_iterator`] for keys that are cheap to iterate again.
+    ///
+    /// [`try_from_iterator`]: Self::try_from_iterator
+    pub fn try_from_single_pass_iterator<T>(keys: T) -> Result<Self, BuildError>
+    where
+        T: IntoIterator<Item = u64>,
+    {
+        let keys: Vec<u64> = keys.into_iter().collect();
+        Self::try_from(keys)
+    }
+
+    /// Try to construct the filter from the union of two key sets. Keys may appear in both sets
+    /// and more than once within a set; the union is deduplicated before construction.
+    ///
+    /// Filters can't be extended, so this still constructs a new filter. Deduplicating sorts a
+    /// copy of both sets, which costs `O((n + m) log(n + m))` time and `8 * (n + m)` bytes on top
+    /// of the construction itself.
+    pub fn try_from_union(a: &[u64], b: &[u64]) -> Result<Self, BuildError> {
+        let mut keys = Vec::with_capacity(a.len() + b.len());
+        keys.extend_from_slice(a);
+        keys.extend_from_slice(b);
+        dedup_sorted(&mut keys);
+        Self::try_from(keys)
+    }
+
+    /// Try to construct the filter from a key iterator, starting the search for a filter seed
+    /// from `seed`. Filters constructed from the same keys and `seed` are identical.
+    pub fn try_from_iterator_with_seed<T>(keys: T, seed: u64) -> Result<Self, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        bfuse_from_impl!(keys fingerprint F, max iter 1_000, rng seed seed)
+    }
+
+    /// Try to construct the filter from a key iterator as [`try_from_iterator`] does, also
+    /// returning [`BuildStats`] about the construction. A large number of attempts or duplicates
+    /// can point to low-quality input, such as keys hashed with a poor hash function.
+    ///
+    /// [`try_from_iterator`]: Self::try_from_iterator
+    pub fn try_from_iterator_with_stats<T>(keys: T) -> Result<(Self, BuildStats), BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        let mut scratch = Scratch::default();
+        let filter = Self::try_from_iterator_with_scratch(keys, &mut scratch)?;
+        Ok((filter, scratch.stats))
+    }
+
+    fn try_from_iterator_with_scratch<T>(keys: T, scratch: &mut Scratch) -> Result<Self, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        bfuse_from_impl!(
+            keys fingerprint F, max iter 1_000, rng seed 1,
+            randomize cfg!(feature = "uniform-random"), scratch scratch,
+            max segment length crate::prelude::bfuse::MAX_SEGMENT_LENGTH
+        )
+    }
+
+    /// Returns the theoretical false positive rate of the filter, `2^-F::BITS`, averaged over all
+    /// keys. See [unused fingerprint slots] for how it varies between keys.
+    ///
+    /// [unused fingerprint slots]: crate#unused-fingerprint-slots
+    pub const fn false_positive_rate(&self) -> f64 {
+        1.0 / (1u128 << F::BITS) as f64
+    }
+
+    /// Returns `true` if the unused fingerprint slots of the filter were filled with random
+    /// values when it was constructed, as with the `uniform-random` feature or a [`FillRng`],
+    /// rather than zeroes.
+    ///
+    /// This is recorded by [`to_bytes`], so it reflects how a filter read with [`from_bytes`] was
+    /// constructed rather than the features of the crate reading it. Filters deserialized with
+    /// serde or bincode, or from bytes written by versions of this crate that didn't record it,
+    /// are taken to have zeroed slots.
+    ///
+    /// [`to_bytes`]: Self::to_bytes
+    /// [`from_bytes`]: Self::from_bytes
+    pub const fn empty_slots_randomized(&self) -> bool {
+        self.empty_slots_randomized
+    }
+
+    /// Returns the number of bits per entry used by the filter if it was constructed from
+    /// `num_keys` keys. All fingerprint slots are counted, including unused ones.
+    pub fn bits_per_entry_over(&self, num_keys: usize) -> f64 {
+        (self.fingerprints.len() as f64) * F::BITS as f64 / (num_keys as f64)
+    }
+
+    /// Returns the number of fingerprints a filter constructed from `num_keys` keys holds,
+    /// without constructing it. The filter occupies about `F::BITS / 8` bytes per fingerprint.
+    ///
+    /// This runs the same sizing as construction, so it can be used to allocate storage for a
+    /// filter, or to reject key sets that are too large, ahead of time.
+    ///
+    /// ```
+    /// # extern crate alloc;
+    /// use core::convert::TryFrom;
+    /// use xorf::{BinaryFuse8, BuildError, Filter};
+    /// # use alloc::vec::Vec;
+    ///
+    /// let keys: Vec<u64> = (0..10_000).collect();
+    /// let len = BinaryFuse8::fingerprint_len_for(keys.len()).unwrap();
+    /// assert_eq!(BinaryFuse8::try_from(&keys).unwrap().len(), len);
+    ///
+    /// assert_eq!(
+    ///     BinaryFuse8::fingerprint_len_for(usize::MAX),
+    ///     Err(BuildError::TooManyKeys)
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BuildError::TooManyKeys`] if a filter can't index `num_keys` keys.
+    pub fn fingerprint_len_for(num_keys: usize) -> Result<usize, BuildError> {
+        if num_keys == 0 {
+            return Ok(0);
+        }
+        dimensions(num_keys, MAX_SEGMENT_LENGTH).map(|dimensions| dimensions.fp_array_len)
+    }
+
+    /// Estimates the number of keys the filter was constructed from, using only its size.
+    ///
+    /// Construction sizes the filter from the number of keys and rounds it up to a whole number
+    /// of segments; this inverts that relationship. Estimates are typically within a few percent
+    /// of the number of keys, but less precise for filters of fewer than a thousand keys.
+    pub fn estimated_capacity(&self) -> usize {
+        if self.fingerprints.is_empty() {
+            return 0;
+        }
+        // Rounding to a whole number of segments adds half a segment on average.
+        let slots = self.fingerprints.len() as f64 - f64::from(self.segment_length) / 2.0;
+        let mut size = slots / 1.125;
+        for _ in 0..8 {
+            size = slots / size_factor(3, (size as u32).max(2));
+        }
+        libm::round(size) as usize
+    }
+
+    /// Returns the number of keys the filter was constructed from, counting duplicate keys once.
+    ///
+    /// The count is only recorded by [`to_bytes`]. Filters deserialized from formats that don't
+    /// record it, such as serde, bincode, the bytes read with [`from_go_bytes`], or bytes written by
+    /// earlier versions of this crate, fall back to the [`estimated_capacity`].
+    ///
+    /// [`to_bytes`]: Self::to_bytes
+    ///
+    /// [`from_go_bytes`]: crate::BinaryFuse8::from_go_bytes
+    /// [`estimated_capacity`]: Self::estimated_capacity
+    pub fn key_count(&self) -> usize {
+        self.key_count.unwrap_or_else(|| self.estimated_capacity())
+    }
+
+    /// Returns the fraction of fingerprint slots that no key was assigned to, using [`key_count`]
+    /// as the number of keys.
+    ///
+    /// Construction needs some slack for keys to be placed, so at least about 11% of the slots of
+    /// large filters, and more of small ones, hold no key's fingerprint. The slots cannot be
+    /// packed away: a key's slots are derived from its hash and the filter's length, so dropping
+    /// or moving slots would change where every key is looked up.
+    ///
+    /// [`key_count`]: Self::key_count
+    pub fn slack_ratio(&self) -> f64 {
+        if self.fingerprints.is_empty() {
+            return 0.0;
+        }
+        let keys = self.key_count().min(self.fingerprints.len());
+        1.0 - keys as f64 / self.fingerprints.len() as f64
+    }
+
+    /// Returns the number of bytes used by the filter, including its fingerprints.
+    pub fn allocated_bytes(&self) -> usize {
+        core::mem::size_of::<Self>() + core::mem::size_of_val(&*self.fingerprints)
+    }
+
+    /// Returns the seed keys are mixed with before hashing them into the filter.
+    pub const fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Returns the number of fingerprints in each segment of the filter. Always a power of two.
+    pub const fn segment_length(&self) -> u32 {
+        self.segment_length
+    }
+
+    /// Returns the mask applied to hashes to index within a segment, `segment_length() - 1`.
+    pub const fn segment_length_mask(&self) -> u32 {
+        self.segment_length_mask
+    }
+
+    /// Returns the number of fingerprints in the segments a key's first index can fall in. The
+    /// filter holds two more segments than these, so that all three indices of a key fit.
+    pub const fn segment_count_length(&self) -> u32 {
+        self.segment_count_length
+    }
+
+    /// Returns the parameters of the filter. Together with the fingerprints, they are all a
+    /// lookup needs; see [`bfuse8_contains`](crate::bfuse8_contains).
+    pub const fn descriptor(&self) -> Descriptor {
+        Descriptor {
+            seed: self.seed,
+            segment_length: self.segment_length,
+            segment_length_mask: self.segment_length_mask,
+            segment_count_length: self.segment_count_length,
+        }
+    }
+
+    /// Returns `true` if the filter contains the key whose hash is `hash`, which must be
+    /// `mix(key, self.seed())`, computed with [`mix`]. Agrees with [`contains`] on the key.
+    ///
+    /// Filters with the same seed hash keys the same way, so the hash of a key looked up in
+    /// several of them can be computed once. Seeded constructors only start the search for a seed
+    /// from the one they are given, so compare the [`seed`]s of constructed filters rather than
+    /// assuming they are shared.
+    ///
+    /// ```
+    /// # extern crate alloc;
+    /// use xorf::{hashing::mix, BinaryFuse8, Filter};
+    /// use core::convert::TryFrom;
+    /// # use alloc::vec::Vec;
+    ///
+    /// let keys: Vec<u64> = (0..10_000).collect();
+    /// let filter = BinaryFuse8::try_from(&keys).unwrap();
+    ///
+    /// let hash = mix(42, filter.seed());
+    /// assert!(filter.contains_mixed(hash));
+    /// ```
+    ///
+    /// [`mix`]: crate::hashing::mix
+    /// [`contains`]: Filter::contains
+    /// [`seed`]: Self::seed
+    #[inline]
+    pub fn contains_mixed(&self, hash: u64) -> bool {
+        bfuse_contains_impl!(mixed hash, self, fingerprint F)
+    }
+
+    /// Looks up `key` as [`contains`] does, also returning the key's fingerprint and the xor of
+    /// the fingerprints stored in the slots it hashes to. See [`Probe`].
+    ///
+    /// [`contains`]: Filter::contains
+    pub fn probe(&self, key: &u64) -> Probe {
+        let hash = mix(*key, self.seed);
+        let fingerprint = F::from_hash(hash);
+        if self.fingerprints.is_empty() {
+            return Probe::new(fingerprint, None);
+        }
+        let (h0, h1, h2) = hash_of_hash(
+            hash,
+            self.segment_length,
+            self.segment_length_mask,
+            self.segment_count_length,
+        );
+        let stored = self.fingerprints[h0 as usize]
+            ^ self.fingerprints[h1 as usize]
+            ^ self.fingerprints[h2 as usize];
+        Probe::new(fingerprint, Some(stored))
+    }
+
+    /// Returns an iterator over the fingerprints of the filter, each widened to a `u64`. Unlike
+    /// the `fingerprints` field, the item type is the same for every filter.
+    pub fn fingerprints(&self) -> impl Iterator<Item = u64> + '_ {
+        self.fingerprints.iter().map(|&f| f.into())
+    }
+
+    /// Returns an iterator over the fingerprint slots of the filter, yielding the index of each
+    /// slot with its fingerprint widened to a `u64`, for tools that inspect the layout of
+    /// fingerprints across filter types.
+    pub fn slots(&self) -> impl Iterator<Item = (usize, u64)> + '_ {
+        self.fingerprints().enumerate()
+    }
+
+    /// Returns a filter with narrower `G` fingerprints that contains every key this filter
+    /// contains, without the keys it was constructed from. The false positive rate rises to that
+    /// of the narrower filter, `2^-G::BITS`.
+    ///
+    /// A key's fingerprint is the low bits of a hash, and a lookup compares it against the xor of
+    /// three slots, so truncating every slot to its low `G::BITS` bits leaves the xor of the
+    /// truncated slots equal to the truncated fingerprint.
+    ///
+    /// ```
+    /// # extern crate alloc;
+    /// use xorf::{BinaryFuse16, BinaryFuse8, Filter};
+    /// use core::convert::TryFrom;
+    /// # use alloc::vec::Vec;
+    ///
+    /// let keys: Vec<u64> = (0..10_000).collect();
+    /// let filter = BinaryFuse16::try_from(&keys).unwrap();
+    ///
+    /// let smaller: BinaryFuse8 = filter.truncate_fingerprints();
+    /// assert!(keys.iter().all(|key| smaller.contains(key)));
+    /// ```
+    ///
+    /// Fingerprints can't be widened, since the bits they would need were never stored.
+    ///
+    /// ```compile_fail
+    /// # extern crate alloc;
+    /// use xorf::{BinaryFuse16, BinaryFuse8};
+    /// use core::convert::TryFrom;
+    /// # use alloc::vec::Vec;
+    ///
+    /// let keys: Vec<u64> = (0..10_000).collect();
+    /// let filter = BinaryFuse8::try_from(&keys).unwrap();
+    ///
+    /// let larger: BinaryFuse16 = filter.truncate_fingerprints(); // doesn't work!
+    /// ```
+    pub fn truncate_fingerprints<G: Fingerprint>(&self) -> BinaryFuse<G> {
+        const { assert!(G::BITS <= F::BITS, "fingerprints can only be truncated") };
+        BinaryFuse {
+            seed: self.seed,
+            segment_length: self.segment_length,
+            segment_length_mask: self.segment_length_mask,
+            segment_count_length: self.segment_count_length,
+            empty_slots_randomized: self.empty_slots_randomized,
+            key_count: self.key_count,
+            // Narrower fingerprints take the low bits of the hash folded to 32 bits. A folded
+            // slot is unchanged by folding again, and an unfolded 64-bit slot is folded the same
+            // way its key's hash is; both are linear, so xors of slots are preserved.
+            fingerprints: self.fingerprints().map(G::from_hash).collect(),
+        }
+    }
+
+    /// Try to construct a filter with `G` fingerprints from `keys`, the keys this filter was
+    /// constructed from, such as to trade a higher false positive rate for a smaller filter.
+    ///
+    /// Filters don't store their keys, so changing the fingerprint width of a filter requires
+    /// constructing it again. If the keys are no longer available, a filter can be narrowed with
+    /// [`truncate_fingerprints`] instead.
+    ///
+    /// [`truncate_fingerprints`]: Self::truncate_fingerprints
+    pub fn rebuild_as<G: Fingerprint>(&self, keys: &[u64]) -> Result<BinaryFuse<G>, BuildError> {
+        debug_assert!(
+            keys.iter().all(|key| self.contains(key)),
+            "Filters can only be rebuilt from the keys they were constructed from."
+        );
+        BinaryFuse::try_from(keys)
+    }
+
+    /// Try to construct the filter from a parallel key iterator, hashing the keys on the
+    /// [`rayon`] thread pool. The constructed filter is the same as the one constructed by
+    /// [`try_from_iterator`] from the same keys.
+    ///
+    /// Note: as with [`try_from_iterator`], the iterator will be iterated over multiple times
+    /// while building the filter.
+    ///
+    /// [`rayon`]: https://docs.rs/rayon
+    /// [`try_from_iterator`]: Self::try_from_iterator
+    #[cfg(feature = "rayon")]
+    pub fn try_from_iterator_parallel<T>(keys: T) -> Result<Self, BuildError>
+    where
+        T: IndexedParallelIterator<Item = u64> + Clone,
+    {
+        bfuse_from_impl!(keys fingerprint F, max iter 1_000, rng seed 1, parallel)
+    }
+
+    /// Try to construct the filter from a slice of keys, checking that the keys are distinct
+    /// first. Unlike [`try_from_iterator`], which only checks this in debug builds, duplicate
+    /// keys are reported with their own error rather than as a failed construction.
+    ///
+    /// The check collects the keys into a set, so it costs extra time and memory over the
+    /// construction itself.
+    ///
+    /// [`try_from_iterator`]: Self::try_from_iterator
+    pub fn try_from_checked(keys: &[u64]) -> Result<Self, BuildError> {
+        if !all_distinct(keys.iter().copied()) {
+            return Err(BuildError::DuplicateKeys);
+        }
+        Self::try_from_iterator(keys.iter().copied())
+    }
+
+    /// Try to construct the filter from a slice of keys, then check that the filter contains
+    /// every key. A missing key would be a bug in construction, which this reports as
+    /// [`BuildError::VerificationFailed`] rather than returning a filter with false negatives.
+    ///
+    /// The check looks up every key once, so it costs about as much as querying the filter for
+    /// each key.
+    pub fn try_from_verified(keys: &[u64]) -> Result<Self, BuildError> {
+        let filter = Self::try_from_iterator(keys.iter().copied())?;
+        if !keys.iter().all(|key| filter.contains(key)) {
+            return Err(BuildError::VerificationFailed);
+        }
+        Ok(filter)
+    }
+
+    /// Serializes the filter to bytes that can be read back with [`from_bytes`], without
+    /// depending on `serde` or `bincode`. The bytes start with a versioned header recording the
+    /// filter type, followed by the filter parameters and fingerprints as little-endian integers.
+    ///
+    /// The byte order does not depend on the platform, so filters serialized on a little-endian
+    /// target can be read on a big-endian one and vice versa.
+    ///
+    /// [`from_bytes`]: Self::from_bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = header::<F>(BINARY_FUSE);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.segment_length.to_le_bytes());
+        bytes.extend_from_slice(&self.segment_length_mask.to_le_bytes());
+        bytes.extend_from_slice(&self.segment_count_length.to_le_bytes());
+        bytes.extend_from_slice(&key_count_bytes(self.key_count));
+        bytes.push(u8::from(self.empty_slots_randomized));
+        write_fingerprints(&mut bytes, &self.fingerprints);
+        bytes
+    }
+
+    /// Deserializes a filter serialized with [`to_bytes`]. Returns an error if `bytes` is not a
+    /// serialized filter of the same type.
+    ///
+    /// [`to_bytes`]: Self::to_bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        let mut reader = Reader::new::<F>(bytes, BINARY_FUSE)?;
+        let seed = reader.u64()?;
+        let segment_length = reader.u32()?;
+        let segment_length_mask = reader.u32()?;
+        let segment_count_length = reader.u32()?;
+        let key_count = if reader.version >= 2 {
+            read_key_count(reader.u64()?)
+                .map_err(|_| "Filter bytes have invalid filter parameters.")?
+        } else {
+            None
+        };
+        let empty_slots_randomized = reader.version >= 3 && reader.u8()? & 1 != 0;
+        let filter = Self {
+            seed,
+            segment_length,
+            segment_length_mask,
+            segment_count_length,
+            empty_slots_randomized,
+            key_count,
+            fingerprints: reader.fingerprints()?,
+        };
+        filter
+            .validate()
+            .map_err(|_| "Filter bytes have invalid filter parameters.")?;
+        Ok(filter)
+    }
+
+    /// Writes the filter to `w` in the format of [`to_bytes`], without collecting the bytes in
+    /// memory first. Fingerprints are converted and written a chunk at a time, so this is suitable
+    /// for filters too large to copy.
+    ///
+    /// [`to_bytes`]: Self::to_bytes
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&header::<F>(BINARY_FUSE))?;
+        w.write_all(&self.seed.to_le_bytes())?;
+        w.write_all(&self.segment_length.to_le_bytes())?;
+        w.write_all(&self.segment_length_mask.to_le_bytes())?;
+        w.write_all(&self.segment_count_length.to_le_bytes())?;
+        w.write_all(&key_count_bytes(self.key_count))?;
+        w.write_all(&[u8::from(self.empty_slots_randomized)])?;
+        write_fingerprints_to(w, &self.fingerprints)
+    }
+
+    /// Reads a filter written with [`write_to`] or [`to_bytes`] from `r`, leaving anything after
+    /// the filter unread. Returns an error of kind [`InvalidData`] if the bytes are not a
+    /// serialized filter of the same type.
+    ///
+    /// [`write_to`]: Self::write_to
+    /// [`to_bytes`]: Self::to_bytes
+    /// [`InvalidData`]: io::ErrorKind::InvalidData
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        let mut reader = StreamReader::new::<F>(r, BINARY_FUSE)?;
+        let seed = reader.u64()?;
+        let segment_length = reader.u32()?;
+        let segment_length_mask = reader.u32()?;
+        let segment_count_length = reader.u32()?;
+        let key_count = if reader.version >= 2 {
+            read_key_count(reader.u64()?)
+                .map_err(|_| invalid_data("Filter bytes have invalid filter parameters."))?
+        } else {
+            None
+        };
+        let empty_slots_randomized = reader.version >= 3 && reader.u8()? & 1 != 0;
+        let filter = Self {
+            seed,
+            segment_length,
+            segment_length_mask,
+            segment_count_length,
+            empty_slots_randomized,
+            key_count,
+            fingerprints: reader.fingerprints()?,
+        };
+        filter
+            .validate()
+            .map_err(|_| invalid_data("Filter bytes have invalid filter parameters."))?;
+        Ok(filter)
+    }
+
+    /// Checks that the filter parameters are consistent with each other and with the number of
+    /// fingerprints. Filters deserialized with `serde` or `bincode` from untrusted data should be
+    /// validated before use, since [`contains`] on an inconsistent filter may panic.
+    ///
+    /// Filters constructed from keys or read with [`from_bytes`] are always valid.
+    ///
+    /// [`contains`]: Filter::contains
+    /// [`from_bytes`]: Self::from_bytes
+    pub const fn validate(&self) -> Result<(), &'static str> {
+        if valid_params(
+            self.segment_length,
+            self.segment_length_mask,
+            self.segment_count_length,
+            self.fingerprints.len(),
+        ) {
+            Ok(())
+        } else {
+            Err("Filter parameters are inconsistent with its fingerprints.")
+        }
+    }
+
+    /// Try to construct the filter from a key iterator, filling unused fingerprint slots with
+    /// values from `rng` rather than zeroes or the `uniform-random` feature's generator seeded
+    /// with the filter seed. See [`FillRng`].
+    ///
+    /// Note: the iterator will be iterated over multiple times while building
+    /// the filter. If using a hash function to map the key, it may be cheaper
+    /// just to create a scratch array of hashed keys that you pass in.
+    pub fn try_from_iterator_with_rng<T, R>(keys: T, rng: &mut R) -> Result<Self, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+        R: FillRng,
+    {
+        bfuse_from_impl!(keys fingerprint F, max iter 1_000, rng seed 1, fill Some(rng))
+    }
+}
+
+impl<F: fmt::Debug> fmt::Debug for BinaryFuse<F> {
+    /// Formats the filter parameters, but only the first few fingerprints.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BinaryFuse")
+            .field("seed", &self.seed)
+            .field("segment_length", &self.segment_length)
+            .field("segment_length_mask", &self.segment_length_mask)
+            .field("segment_count_length", &self.segment_count_length)
+            .field("fingerprints", &FingerprintsPreview(&self.fingerprints))
+            .finish()
+    }
+}
+
+impl<F: Fingerprint> fmt::Display for BinaryFuse<F> {
+    /// Summarizes the filter on one line, with bits per entry computed over the [`key_count`].
+    ///
+    /// [`key_count`]: Self::key_count
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_summary(
+            f,
+            "BinaryFuse",
+            F::BITS,
+            self.fingerprints.len(),
+            self.key_count(),
+        )
+    }
+}
+
+impl<F: Fingerprint> TryFrom<&[u64]> for BinaryFuse<F> {
+    type Error = BuildError;
+
+    fn try_from(keys: &[u64]) -> Result<Self, Self::Error> {
+        Self::try_from_iterator(keys.iter().copied())
+    }
+}
+
+impl<F: Fingerprint, const N: usize> TryFrom<&[u64; N]> for BinaryFuse<F> {
+    type Error = BuildError;
+
+    fn try_from(keys: &[u64; N]) -> Result<Self, Self::Error> {
+        Self::try_from(&keys[..])
+    }
+}
+
+impl<F: Fingerprint> TryFrom<&Vec<u64>> for BinaryFuse<F> {
+    type Error = BuildError;
+
+    fn try_from(v: &Vec<u64>) -> Result<Self, Self::Error> {
+        Self::try_from_iterator(v.iter().copied())
+    }
+}
+
+impl<F: Fingerprint> TryFrom<Vec<u64>> for BinaryFuse<F> {
+    type Error = BuildError;
+
+    fn try_from(v: Vec<u64>) -> Result<Self, Self::Error> {
+        Self::try_from_iterator(v.iter().copied())
+    }
+}
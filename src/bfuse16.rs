@@ -1,16 +1,12 @@
 //! Implements BinaryFuse16 filters.
 
-use crate::{bfuse_contains_impl, bfuse_from_impl, Filter};
-use alloc::{boxed::Box, vec::Vec};
-use core::convert::TryFrom;
+use crate::bfuse::BinaryFuse;
 
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
-
-#[cfg(feature = "bincode")]
-use bincode::{Decode, Encode};
+#[cfg(feature = "simd")]
+use {crate::bfuse_contains_simd_impl, alloc::vec::Vec};
 
 /// A `BinaryFuse16` filter is an Xor-like filter with 16-bit fingerprints arranged in a binary-partitioned [fuse graph].
+///
 /// `BinaryFuse16`s are similar to [`Fuse16`]s, but their construction is faster, uses less
 /// memory, and is more likely to succeed.
 ///
@@ -39,7 +35,7 @@ use bincode::{Decode, Encode};
 /// }
 ///
 /// // bits per entry
-/// let bpe = (filter.len() as f64) * 16.0 / (SAMPLE_SIZE as f64);
+/// let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 /// assert!(bpe < 18.1, "Bits per entry is {}", bpe);
 ///
 /// // false positive rate
@@ -51,72 +47,23 @@ use bincode::{Decode, Encode};
 /// assert!(fp_rate < 0.0025, "False positive rate is {}", fp_rate);
 /// ```
 ///
+/// The default `BinaryFuse16` is empty, the same as a filter constructed from no keys.
+///
 /// Serializing and deserializing `BinaryFuse16` filters can be enabled with the [`serde`] feature (or [`bincode`] for bincode).
 ///
 /// [fuse graph]: https://arxiv.org/abs/1907.04749
 /// [`Fuse16`]: crate::Fuse16
 /// [`serde`]: http://serde.rs
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "bincode", derive(Encode, Decode))]
-#[derive(Debug, Clone)]
-pub struct BinaryFuse16 {
-    seed: u64,
-    segment_length: u32,
-    segment_length_mask: u32,
-    segment_count_length: u32,
-    /// The fingerprints for the filter
-    pub fingerprints: Box<[u16]>,
-}
-
-impl Filter<u64> for BinaryFuse16 {
-    /// Returns `true` if the filter contains the specified key.
-    /// Has a false positive rate of <0.4%.
-    /// Has no false negatives.
-    fn contains(&self, key: &u64) -> bool {
-        bfuse_contains_impl!(*key, self, fingerprint u16)
-    }
-
-    fn len(&self) -> usize {
-        self.fingerprints.len()
-    }
-}
+pub type BinaryFuse16 = BinaryFuse<u16>;
 
+#[cfg(feature = "simd")]
 impl BinaryFuse16 {
-    /// Try to construct the filter from a key iterator. Can be used directly
-    /// if you don't have a contiguous array of u64 keys.
+    /// Returns whether the filter contains each of `keys`, hashing several keys at a time with
+    /// SIMD instructions. Agrees with [`contains`] on every key.
     ///
-    /// Note: the iterator will be iterated over multiple times while building
-    /// the filter. If using a hash function to map the key, it may be cheaper
-    /// just to create a scratch array of hashed keys that you pass in.
-    pub fn try_from_iterator<T>(keys: T) -> Result<Self, &'static str>
-    where
-        T: ExactSizeIterator<Item = u64> + Clone,
-    {
-        bfuse_from_impl!(keys fingerprint u16, max iter 1_000)
-    }
-}
-
-impl TryFrom<&[u64]> for BinaryFuse16 {
-    type Error = &'static str;
-
-    fn try_from(keys: &[u64]) -> Result<Self, Self::Error> {
-        Self::try_from_iterator(keys.iter().copied())
-    }
-}
-
-impl TryFrom<&Vec<u64>> for BinaryFuse16 {
-    type Error = &'static str;
-
-    fn try_from(v: &Vec<u64>) -> Result<Self, Self::Error> {
-        Self::try_from_iterator(v.iter().copied())
-    }
-}
-
-impl TryFrom<Vec<u64>> for BinaryFuse16 {
-    type Error = &'static str;
-
-    fn try_from(v: Vec<u64>) -> Result<Self, Self::Error> {
-        Self::try_from_iterator(v.iter().copied())
+    /// [`contains`]: crate::Filter::contains
+    pub fn contains_simd(&self, keys: &[u64]) -> Vec<bool> {
+        bfuse_contains_simd_impl!(keys, self, fingerprint u16)
     }
 }
 
@@ -148,11 +95,146 @@ mod test {
         let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
 
         let filter = BinaryFuse16::try_from(&keys).unwrap();
-        let bpe = (filter.len() as f64) * 16.0 / (SAMPLE_SIZE as f64);
+        let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 
         assert!(bpe < 18.1, "Bits per entry is {}", bpe);
     }
 
+    #[test]
+    #[cfg(feature = "simd")]
+    fn test_contains_simd() {
+        // Not a multiple of the lane count, so the scalar tail is exercised too.
+        const SAMPLE_SIZE: usize = 100_003;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse16::try_from(&keys).unwrap();
+
+        assert!(filter.contains_simd(&keys).iter().all(|&found| found));
+
+        let queries: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        let found = filter.contains_simd(&queries);
+        assert_eq!(found.len(), SAMPLE_SIZE);
+        for (key, found) in queries.iter().zip(found) {
+            assert_eq!(filter.contains(key), found);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_initialization_parallel() {
+        use rayon::prelude::*;
+
+        const SAMPLE_SIZE: usize = 1_000_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse16::try_from_iterator_parallel(keys.par_iter().copied()).unwrap();
+        let serial = BinaryFuse16::try_from(&keys).unwrap();
+        assert_eq!(filter.seed, serial.seed);
+        assert_eq!(filter.fingerprints, serial.fingerprints);
+
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_try_from_checked() {
+        let keys: Vec<u64> = (0..1_000).collect();
+        let filter = BinaryFuse16::try_from_checked(&keys).unwrap();
+        for key in &keys {
+            assert!(filter.contains(key));
+        }
+
+        let duplicated: Vec<u64> = keys.iter().chain(&keys[..10]).copied().collect();
+        assert_eq!(
+            BinaryFuse16::try_from_checked(&duplicated).unwrap_err(),
+            crate::BuildError::DuplicateKeys
+        );
+    }
+
+    #[test]
+    fn test_contains_converted() {
+        let ids: Vec<u32> = (0..10_000).map(|id| id * 3).collect();
+        let filter = BinaryFuse16::try_from_iterator(ids.iter().map(|&id| u64::from(id))).unwrap();
+
+        for &id in &ids {
+            assert!(filter.contains_converted(id));
+            assert!(filter.contains(&u64::from(id)));
+        }
+        for id in (0..10_000u32).map(|id| id * 3 + 1) {
+            assert_eq!(
+                filter.contains_converted(id),
+                filter.contains(&u64::from(id))
+            );
+        }
+    }
+
+    #[test]
+    fn test_default() {
+        #[derive(Default)]
+        struct Holder {
+            filter: BinaryFuse16,
+        }
+
+        let holder = Holder::default();
+        assert_eq!(holder.filter.len(), 0);
+        assert!((0..1_000).all(|key| !holder.filter.contains(&key)));
+    }
+
+    #[test]
+    fn test_fingerprints() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter = BinaryFuse16::try_from(&keys).unwrap();
+
+        assert_eq!(filter.fingerprints().count(), filter.len());
+        assert!(filter
+            .fingerprints()
+            .zip(filter.fingerprints.iter())
+            .all(|(wide, &f)| wide == f as u64));
+    }
+
+    #[test]
+    fn test_slots() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter = BinaryFuse16::try_from(&keys).unwrap();
+
+        assert_eq!(filter.slots().count(), filter.len());
+        assert!(filter
+            .slots()
+            .all(|(i, fingerprint)| fingerprint == filter.fingerprints[i] as u64));
+    }
+
+    #[test]
+    fn test_truncate_fingerprints() {
+        use crate::{BinaryFuse64, BinaryFuse8};
+
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        let filter = BinaryFuse16::try_from(&keys).unwrap();
+
+        let truncated: BinaryFuse8 = filter.truncate_fingerprints();
+        assert_eq!(truncated.len(), filter.len());
+        assert_eq!(truncated.descriptor(), filter.descriptor());
+        for key in keys.iter() {
+            assert!(truncated.contains(key));
+        }
+
+        let rebuilt: BinaryFuse8 = filter.rebuild_as(&keys).unwrap();
+        for key in keys.iter() {
+            assert!(rebuilt.contains(key));
+        }
+        // 64-bit fingerprints are whole hashes, so they are folded rather than only truncated.
+        let wide: BinaryFuse8 = BinaryFuse64::try_from(&keys)
+            .unwrap()
+            .truncate_fingerprints();
+        for key in keys.iter() {
+            assert!(wide.contains(key));
+        }
+    }
+
     #[test]
     fn test_false_positives() {
         const SAMPLE_SIZE: usize = 1_000_000;
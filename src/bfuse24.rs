@@ -0,0 +1,205 @@
+//! Implements BinaryFuse24 filters.
+
+use crate::{bfuse::BinaryFuse, U24};
+
+/// A `BinaryFuse24` filter is an Xor-like filter with 24-bit fingerprints arranged in a binary-partitioned [fuse graph].
+///
+/// A `BinaryFuse24` filter uses ≈27 bits per entry of the set is it constructed from, and has a false
+/// positive rate of 1/2^24 =~ 1/16 million, between those of a [`BinaryFuse16`] and a
+/// [`BinaryFuse32`]. As with other probabilistic filters, a higher number of entries decreases
+/// the bits per entry but increases the false positive rate.
+///
+/// Fingerprints are stored as packed three-byte [`U24`]s, so they take up no more space than
+/// they need.
+///
+/// A `BinaryFuse24` is constructed from a set of 64-bit unsigned integers and is immutable.
+/// Construction may fail, but usually only if there are duplicate keys.
+///
+/// ```
+/// # extern crate alloc;
+/// use xorf::{Filter, BinaryFuse24};
+/// use core::convert::TryFrom;
+/// # use alloc::vec::Vec;
+/// # use rand::Rng;
+///
+/// # let mut rng = rand::thread_rng();
+/// const SAMPLE_SIZE: usize = 1_000_000;
+/// let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+/// let filter = BinaryFuse24::try_from(&keys).unwrap();
+///
+/// // no false negatives
+/// for key in keys {
+///     assert!(filter.contains(&key));
+/// }
+///
+/// // bits per entry
+/// let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
+/// assert!(bpe < 27.2, "Bits per entry is {}", bpe);
+///
+/// // false positive rate
+/// let false_positives: usize = (0..SAMPLE_SIZE)
+///     .map(|_| rng.gen())
+///     .filter(|n| filter.contains(n))
+///     .count();
+/// let fp_rate: f64 = (false_positives * 100) as f64 / SAMPLE_SIZE as f64;
+/// assert!(fp_rate < 0.0005, "False positive rate is {}", fp_rate);
+/// ```
+///
+/// The default `BinaryFuse24` is empty, the same as a filter constructed from no keys.
+///
+/// Serializing and deserializing `BinaryFuse24` filters can be enabled with the [`serde`] feature (or [`bincode`] for bincode).
+///
+/// [fuse graph]: https://arxiv.org/abs/1907.04749
+/// [`BinaryFuse16`]: crate::BinaryFuse16
+/// [`BinaryFuse32`]: crate::BinaryFuse32
+/// [`serde`]: http://serde.rs
+pub type BinaryFuse24 = BinaryFuse<U24>;
+
+#[cfg(test)]
+mod test {
+    use crate::{BinaryFuse24, Filter};
+    use core::convert::TryFrom;
+
+    use alloc::vec::Vec;
+    use rand::Rng;
+
+    #[test]
+    fn test_initialization() {
+        const SAMPLE_SIZE: usize = 1_000_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse24::try_from(&keys).unwrap();
+
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_bits_per_entry() {
+        const SAMPLE_SIZE: usize = 1_000_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse24::try_from(&keys).unwrap();
+        let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
+
+        assert!(bpe < 27.2, "Bits per entry is {}", bpe);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_initialization_parallel() {
+        use rayon::prelude::*;
+
+        const SAMPLE_SIZE: usize = 1_000_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse24::try_from_iterator_parallel(keys.par_iter().copied()).unwrap();
+        let serial = BinaryFuse24::try_from(&keys).unwrap();
+        assert_eq!(filter.seed, serial.seed);
+        assert_eq!(filter.fingerprints, serial.fingerprints);
+
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_try_from_checked() {
+        let keys: Vec<u64> = (0..1_000).collect();
+        let filter = BinaryFuse24::try_from_checked(&keys).unwrap();
+        for key in &keys {
+            assert!(filter.contains(key));
+        }
+
+        let duplicated: Vec<u64> = keys.iter().chain(&keys[..10]).copied().collect();
+        assert_eq!(
+            BinaryFuse24::try_from_checked(&duplicated).unwrap_err(),
+            crate::BuildError::DuplicateKeys
+        );
+    }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter = BinaryFuse24::try_from(&keys).unwrap();
+        assert_eq!(
+            core::mem::size_of_val(&*filter.fingerprints),
+            filter.len() * 3
+        );
+
+        let bytes = filter.to_bytes();
+        let deserialized = BinaryFuse24::from_bytes(&bytes).unwrap();
+        assert_eq!(filter, deserialized);
+        assert_eq!(
+            crate::BinaryFuse32::from_bytes(&bytes).unwrap_err(),
+            "Filter bytes are of a different filter type or format version."
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let mut filter = BinaryFuse24::try_from(&keys).unwrap();
+        assert_eq!(filter.validate(), Ok(()));
+        assert_eq!(BinaryFuse24::default().validate(), Ok(()));
+
+        filter.fingerprints = filter.fingerprints[1..].into();
+        assert_eq!(
+            filter.validate(),
+            Err("Filter parameters are inconsistent with its fingerprints.")
+        );
+        assert_eq!(
+            BinaryFuse24::from_bytes(&filter.to_bytes()).unwrap_err(),
+            "Filter bytes have invalid filter parameters."
+        );
+    }
+
+    #[test]
+    fn test_contains_many() {
+        // Large enough for lookups to be prefetched.
+        const SAMPLE_SIZE: usize = 1_000_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse24::try_from(&keys).unwrap();
+
+        let mut out = vec![false; SAMPLE_SIZE];
+        filter.contains_many(&keys, &mut out);
+        assert!(out.iter().all(|&found| found));
+
+        let queries: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        filter.contains_many(&queries, &mut out);
+        for (key, found) in queries.iter().zip(out) {
+            assert_eq!(filter.contains(key), found);
+        }
+    }
+
+    #[test]
+    fn test_false_positives() {
+        const SAMPLE_SIZE: usize = 1_000_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse24::try_from(&keys).unwrap();
+
+        let false_positives: usize = (0..SAMPLE_SIZE)
+            .map(|_| rng.gen())
+            .filter(|n| filter.contains(n))
+            .count();
+        let fp_rate: f64 = (false_positives * 100) as f64 / SAMPLE_SIZE as f64;
+        assert!(fp_rate < 0.0005, "False positive rate is {}", fp_rate);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(
+        expected = "Binary Fuse filters must be constructed from a collection containing all distinct keys."
+    )]
+    fn test_debug_assert_duplicates() {
+        let _ = BinaryFuse24::try_from(vec![1, 2, 1]);
+    }
+}
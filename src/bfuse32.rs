@@ -1,16 +1,9 @@
-//! Implements BinaryFuse16 filters.
+//! Implements BinaryFuse32 filters.
 
-use crate::{bfuse_contains_impl, bfuse_from_impl, Filter};
-use alloc::{boxed::Box, vec::Vec};
-use core::convert::TryFrom;
-
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
-
-#[cfg(feature = "bincode")]
-use bincode::{Decode, Encode};
+use crate::bfuse::BinaryFuse;
 
 /// A `BinaryFuse32` filter is an Xor-like filter with 32-bit fingerprints arranged in a binary-partitioned [fuse graph].
+///
 /// `BinaryFuse32`s are similar to [`Fuse32`]s, but their construction is faster, uses less
 /// memory, and is more likely to succeed.
 ///
@@ -40,7 +33,7 @@ use bincode::{Decode, Encode};
 /// }
 ///
 /// // bits per entry
-/// let bpe = (filter.len() as f64) * 32.0 / (SAMPLE_SIZE as f64);
+/// let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 /// assert!(bpe < 36.2, "Bits per entry is {}", bpe);
 ///
 /// // false positive rate
@@ -52,74 +45,14 @@ use bincode::{Decode, Encode};
 /// assert!(fp_rate < 0.0000000000000001, "False positive rate is {}", fp_rate);
 /// ```
 ///
+/// The default `BinaryFuse32` is empty, the same as a filter constructed from no keys.
+///
 /// Serializing and deserializing `BinaryFuse32` filters can be enabled with the [`serde`] feature (or [`bincode`] for bincode).
 ///
 /// [fuse graph]: https://arxiv.org/abs/1907.04749
 /// [`Fuse32`]: crate::Fuse32
 /// [`serde`]: http://serde.rs
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "bincode", derive(Encode, Decode))]
-#[derive(Debug, Clone)]
-pub struct BinaryFuse32 {
-    seed: u64,
-    segment_length: u32,
-    segment_length_mask: u32,
-    segment_count_length: u32,
-    /// The fingerprints for the filter
-    pub fingerprints: Box<[u32]>,
-}
-
-impl Filter<u64> for BinaryFuse32 {
-    /// Returns `true` if the filter contains the specified key.
-    /// Has a false positive rate of <0.4%.
-    /// Has no false negatives.
-    fn contains(&self, key: &u64) -> bool {
-        bfuse_contains_impl!(*key, self, fingerprint u32)
-    }
-
-    fn len(&self) -> usize {
-        self.fingerprints.len()
-    }
-}
-
-impl BinaryFuse32 {
-    /// Try to construct the filter from a key iterator. Can be used directly
-    /// if you don't have a contiguous array of u64 keys.
-    ///
-    /// Note: the iterator will be iterated over multiple times while building
-    /// the filter. If using a hash function to map the key, it may be cheaper
-    /// just to create a scratch array of hashed keys that you pass in.
-    pub fn try_from_iterator<T>(keys: T) -> Result<Self, &'static str>
-    where
-        T: ExactSizeIterator<Item = u64> + Clone,
-    {
-        bfuse_from_impl!(keys fingerprint u32, max iter 1_000)
-    }
-}
-
-impl TryFrom<&[u64]> for BinaryFuse32 {
-    type Error = &'static str;
-
-    fn try_from(keys: &[u64]) -> Result<Self, Self::Error> {
-        Self::try_from_iterator(keys.iter().copied())
-    }
-}
-
-impl TryFrom<&Vec<u64>> for BinaryFuse32 {
-    type Error = &'static str;
-
-    fn try_from(v: &Vec<u64>) -> Result<Self, Self::Error> {
-        Self::try_from_iterator(v.iter().copied())
-    }
-}
-
-impl TryFrom<Vec<u64>> for BinaryFuse32 {
-    type Error = &'static str;
-
-    fn try_from(v: Vec<u64>) -> Result<Self, Self::Error> {
-        Self::try_from_iterator(v.iter().copied())
-    }
-}
+pub type BinaryFuse32 = BinaryFuse<u32>;
 
 #[cfg(test)]
 mod test {
@@ -149,11 +82,83 @@ mod test {
         let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
 
         let filter = BinaryFuse32::try_from(&keys).unwrap();
-        let bpe = (filter.len() as f64) * 32.0 / (SAMPLE_SIZE as f64);
+        let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 
         assert!(bpe < 36.2, "Bits per entry is {}", bpe);
     }
 
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_initialization_parallel() {
+        use rayon::prelude::*;
+
+        const SAMPLE_SIZE: usize = 1_000_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse32::try_from_iterator_parallel(keys.par_iter().copied()).unwrap();
+        let serial = BinaryFuse32::try_from(&keys).unwrap();
+        assert_eq!(filter.seed, serial.seed);
+        assert_eq!(filter.fingerprints, serial.fingerprints);
+
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_try_from_checked() {
+        let keys: Vec<u64> = (0..1_000).collect();
+        let filter = BinaryFuse32::try_from_checked(&keys).unwrap();
+        for key in &keys {
+            assert!(filter.contains(key));
+        }
+
+        let duplicated: Vec<u64> = keys.iter().chain(&keys[..10]).copied().collect();
+        assert_eq!(
+            BinaryFuse32::try_from_checked(&duplicated).unwrap_err(),
+            crate::BuildError::DuplicateKeys
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let mut filter = BinaryFuse32::try_from(&keys).unwrap();
+        assert_eq!(filter.validate(), Ok(()));
+        assert_eq!(BinaryFuse32::default().validate(), Ok(()));
+
+        filter.fingerprints = filter.fingerprints[1..].into();
+        assert_eq!(
+            filter.validate(),
+            Err("Filter parameters are inconsistent with its fingerprints.")
+        );
+        assert_eq!(
+            BinaryFuse32::from_bytes(&filter.to_bytes()).unwrap_err(),
+            "Filter bytes have invalid filter parameters."
+        );
+    }
+
+    #[test]
+    fn test_contains_many() {
+        // Large enough for lookups to be prefetched.
+        const SAMPLE_SIZE: usize = 1_000_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse32::try_from(&keys).unwrap();
+
+        let mut out = vec![false; SAMPLE_SIZE];
+        filter.contains_many(&keys, &mut out);
+        assert!(out.iter().all(|&found| found));
+
+        let queries: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        filter.contains_many(&queries, &mut out);
+        for (key, found) in queries.iter().zip(out) {
+            assert_eq!(filter.contains(key), found);
+        }
+    }
+
     #[test]
     fn test_false_positives() {
         const SAMPLE_SIZE: usize = 1_000_000;
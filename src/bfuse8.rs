@@ -1,16 +1,22 @@
 //! Implements BinaryFuse8 filters.
 
-use crate::{bfuse_contains_impl, bfuse_from_impl, Filter};
+use crate::{
+    bfuse::{BinaryFuse, BuildStats},
+    bfuse_from_impl,
+    prelude::{
+        bfuse::{hash_of_hash, Scratch, MAX_SEGMENT_LENGTH},
+        mix,
+    },
+    BuildError, FillRng,
+};
 use alloc::{boxed::Box, vec::Vec};
-use core::convert::TryFrom;
+use core::convert::TryInto;
 
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
-
-#[cfg(feature = "bincode")]
-use bincode::{Decode, Encode};
+#[cfg(feature = "simd")]
+use crate::bfuse_contains_simd_impl;
 
 /// A `BinaryFuse8` filter is an Xor-like filter with 8-bit fingerprints arranged in a binary-partitioned [fuse graph].
+///
 /// `BinaryFuse8`s are similar to [`Fuse8`]s, but their construction is faster, uses less
 /// memory, and is more likely to succeed.
 ///
@@ -39,7 +45,7 @@ use bincode::{Decode, Encode};
 /// }
 ///
 /// // bits per entry
-/// let bpe = (filter.len() as f64) * 8.0 / (SAMPLE_SIZE as f64);
+/// let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 /// assert!(bpe < 9.1, "Bits per entry is {}", bpe);
 ///
 /// // false positive rate
@@ -51,79 +57,285 @@ use bincode::{Decode, Encode};
 /// assert!(fp_rate < 0.406, "False positive rate is {}", fp_rate);
 /// ```
 ///
+/// The default `BinaryFuse8` is empty, the same as a filter constructed from no keys.
+///
 /// Serializing and deserializing `BinaryFuse8` filters can be enabled with the [`serde`] feature (or [`bincode`] for bincode).
-
 ///
 /// [fuse graph]: https://arxiv.org/abs/1907.04749
 /// [`Fuse8`]: crate::Fuse8
 /// [`serde`]: http://serde.rs
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "bincode", derive(Encode, Decode))]
+pub type BinaryFuse8 = BinaryFuse<u8>;
+
+impl BinaryFuse8 {
+    /// Returns whether the filter contains each of `keys`, hashing several keys at a time with
+    /// SIMD instructions. Agrees with [`contains`] on every key.
+    ///
+    /// [`contains`]: crate::Filter::contains
+    #[cfg(feature = "simd")]
+    pub fn contains_simd(&self, keys: &[u64]) -> Vec<bool> {
+        bfuse_contains_simd_impl!(keys, self, fingerprint u8)
+    }
+
+    /// Deserializes a filter built by the Go [`xorfilter`] library, whose construction this
+    /// filter is ported from. `bytes` holds the `Seed`, `SegmentLength`, `SegmentLengthMask`, and
+    /// `SegmentCountLength` fields of the Go `BinaryFuse8` as little-endian integers, followed by
+    /// its `Fingerprints`, as written by `binary.Write` with `binary.LittleEndian`. Keys are hashed
+    /// the same way in both libraries, so lookups agree with the Go filter.
+    ///
+    /// Returns an error if the parameters are inconsistent with each other or with the number of
+    /// fingerprints.
+    ///
+    /// [`xorfilter`]: https://github.com/FastFilter/xorfilter
+    pub fn from_go_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() < 20 {
+            return Err("Filter bytes are truncated.");
+        }
+        let (header, fingerprints) = bytes.split_at(20);
+        let seed = u64::from_le_bytes(header[..8].try_into().unwrap());
+        let segment_length = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        let segment_length_mask = u32::from_le_bytes(header[12..16].try_into().unwrap());
+        let segment_count_length = u32::from_le_bytes(header[16..].try_into().unwrap());
+        let filter = Self {
+            seed,
+            segment_length,
+            segment_length_mask,
+            segment_count_length,
+            empty_slots_randomized: false,
+            key_count: None,
+            fingerprints: fingerprints.into(),
+        };
+        filter
+            .validate()
+            .map_err(|_| "Filter bytes have invalid filter parameters.")?;
+        Ok(filter)
+    }
+
+    fn try_from_builder<T>(keys: T, builder: &mut BinaryFuse8Builder) -> Result<Self, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        bfuse_from_impl!(
+            keys fingerprint u8,
+            max iter builder.max_iterations,
+            rng seed builder.seed,
+            randomize builder.randomize_empty_slots,
+            scratch &mut builder.scratch,
+            max segment length builder.max_segment_length
+        )
+    }
+
+    fn try_from_builder_with_rng<T, R>(
+        keys: T,
+        builder: &mut BinaryFuse8Builder,
+        rng: &mut R,
+    ) -> Result<Self, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+        R: FillRng,
+    {
+        bfuse_from_impl!(
+            keys fingerprint u8,
+            max iter builder.max_iterations,
+            rng seed builder.seed,
+            fill Some(rng),
+            scratch &mut builder.scratch,
+            max segment length builder.max_segment_length
+        )
+    }
+}
+
+/// Builds [`BinaryFuse8`] filters with construction options that are otherwise fixed.
+///
+/// ```
+/// # extern crate alloc;
+/// use xorf::{BinaryFuse8Builder, Filter};
+/// # use alloc::vec::Vec;
+///
+/// let keys: Vec<u64> = (0..10_000).collect();
+/// let filter = BinaryFuse8Builder::new()
+///     .seed(42)
+///     .max_iterations(100)
+///     .build(keys.iter().copied())
+///     .unwrap();
+///
+/// for key in keys {
+///     assert!(filter.contains(&key));
+/// }
+/// ```
 #[derive(Debug, Clone)]
-pub struct BinaryFuse8 {
+pub struct BinaryFuse8Builder {
     seed: u64,
-    segment_length: u32,
-    segment_length_mask: u32,
-    segment_count_length: u32,
-    /// The fingerprints for the filter
-    pub fingerprints: Box<[u8]>,
+    max_iterations: usize,
+    max_segment_length: u32,
+    randomize_empty_slots: bool,
+    scratch: Scratch,
 }
 
-impl Filter<u64> for BinaryFuse8 {
-    /// Returns `true` if the filter contains the specified key.
-    /// Has a false positive rate of <0.4%.
-    /// Has no false negatives.
-    fn contains(&self, key: &u64) -> bool {
-        bfuse_contains_impl!(*key, self, fingerprint u8)
+impl BinaryFuse8Builder {
+    /// Creates a builder with the options used by [`BinaryFuse8::try_from_iterator`].
+    pub const fn new() -> Self {
+        Self {
+            seed: 1,
+            max_iterations: 1_000,
+            max_segment_length: MAX_SEGMENT_LENGTH,
+            randomize_empty_slots: cfg!(feature = "uniform-random"),
+            scratch: Scratch {
+                retain: true,
+                alone: Vec::new(),
+                t2count: Vec::new(),
+                t2hash: Vec::new(),
+                reverse_h: Vec::new(),
+                reverse_order: Vec::new(),
+                start_pos: Vec::new(),
+                stats: BuildStats {
+                    attempts: 0,
+                    duplicates: 0,
+                    final_seed: 0,
+                },
+            },
+        }
     }
 
-    fn len(&self) -> usize {
-        self.fingerprints.len()
+    /// Sets the seed the search for a filter seed starts from. See
+    /// [`BinaryFuse8::try_from_iterator_with_seed`].
+    pub const fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
     }
-}
 
-impl BinaryFuse8 {
-    /// Try to construct the filter from a key iterator. Can be used directly
-    /// if you don't have a contiguous array of u64 keys.
+    /// Sets the number of filter seeds to try before giving up on construction.
+    pub const fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Sets the largest segment length filters are constructed with, rounded down to a power of
+    /// two. Defaults to 262144.
+    ///
+    /// The segment length grows with the number of keys, to 8192 for a million keys, and only
+    /// reaches the default cap for over a hundred million keys. A filter has two segments more
+    /// fingerprint slots than its keys need, so a smaller cap saves memory on filters of many
+    /// keys, and construction works on a smaller region of memory at a time. But keys are placed
+    /// less reliably in shorter segments: capping the length more than about a factor of two below
+    /// the length chosen for the number of keys makes construction fail. A larger cap only changes
+    /// filters of over a hundred million keys.
+    pub const fn max_segment_length(mut self, max_segment_length: u32) -> Self {
+        self.max_segment_length = match max_segment_length.checked_ilog2() {
+            Some(log) => 1 << log,
+            None => 1,
+        };
+        self
+    }
+
+    /// Sets whether unused fingerprint slots are filled with random values rather than zero.
+    /// Defaults to `true` with the `uniform-random` feature and `false` otherwise. Random slots
+    /// make the false positive rate uniform across keys, at the cost of slower construction. They
+    /// are generated from the filter seed, so they don't need `rand`.
+    pub const fn randomize_empty_slots(mut self, randomize: bool) -> Self {
+        self.randomize_empty_slots = randomize;
+        self
+    }
+
+    /// Constructs a filter from a key iterator with the builder's options.
+    ///
+    /// The builder keeps the buffers used during construction and reuses them in later builds,
+    /// which saves reallocating them when building many filters. Filters are the same as if they
+    /// were built by a fresh builder.
     ///
     /// Note: the iterator will be iterated over multiple times while building
     /// the filter. If using a hash function to map the key, it may be cheaper
     /// just to create a scratch array of hashed keys that you pass in.
-    pub fn try_from_iterator<T>(keys: T) -> Result<Self, &'static str>
+    pub fn build<T>(&mut self, keys: T) -> Result<BinaryFuse8, BuildError>
     where
         T: ExactSizeIterator<Item = u64> + Clone,
     {
-        bfuse_from_impl!(keys fingerprint u8, max iter 1_000)
+        BinaryFuse8::try_from_builder(keys, self)
     }
-}
 
-impl TryFrom<&[u64]> for BinaryFuse8 {
-    type Error = &'static str;
-
-    fn try_from(keys: &[u64]) -> Result<Self, Self::Error> {
-        Self::try_from_iterator(keys.iter().copied())
+    /// Constructs a filter from a key iterator with the builder's options, filling unused
+    /// fingerprint slots with values from `rng`. See [`FillRng`].
+    pub fn build_with_rng<T, R>(&mut self, keys: T, rng: &mut R) -> Result<BinaryFuse8, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+        R: FillRng,
+    {
+        BinaryFuse8::try_from_builder_with_rng(keys, self, rng)
     }
 }
 
-impl TryFrom<&Vec<u64>> for BinaryFuse8 {
-    type Error = &'static str;
-
-    fn try_from(v: &Vec<u64>) -> Result<Self, Self::Error> {
-        Self::try_from_iterator(v.iter().copied())
+impl Default for BinaryFuse8Builder {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl TryFrom<Vec<u64>> for BinaryFuse8 {
-    type Error = &'static str;
-
-    fn try_from(v: Vec<u64>) -> Result<Self, Self::Error> {
-        Self::try_from_iterator(v.iter().copied())
+/// Returns `true` if a `BinaryFuse8` filter with the given parameters and fingerprints contains
+/// `key`, as [`Filter::contains`] does.
+///
+/// Unlike [`Filter::contains`], it can be evaluated in a `const` context, so a filter embedded in
+/// a binary as constants can be queried without constructing a `BinaryFuse8` at startup.
+///
+/// The parameters are those returned by [`seed`], [`segment_length`], [`segment_length_mask`],
+/// and [`segment_count_length`], which a build script can write out alongside the fingerprints.
+///
+/// ```
+/// use xorf::bfuse8_contains;
+///
+/// // Written out from a `BinaryFuse8` constructed from the keys 1, 2, and 3.
+/// const SEED: u64 = 0x910a_2dec_8902_5cc1;
+/// const SEGMENT_LENGTH: u32 = 8;
+/// const SEGMENT_LENGTH_MASK: u32 = 7;
+/// const SEGMENT_COUNT_LENGTH: u32 = 8;
+/// const FINGERPRINTS: &[u8] = &[
+///     0, 0, 0, 202, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 184, 0, 0, 0, 0, 187, 0, 0,
+/// ];
+///
+/// const fn contains(key: u64) -> bool {
+///     bfuse8_contains(
+///         key,
+///         SEED,
+///         SEGMENT_LENGTH,
+///         SEGMENT_LENGTH_MASK,
+///         SEGMENT_COUNT_LENGTH,
+///         FINGERPRINTS,
+///     )
+/// }
+///
+/// const CONTAINS_ALL: bool = contains(1) && contains(2) && contains(3);
+/// assert!(CONTAINS_ALL);
+/// ```
+///
+/// [`Filter::contains`]: crate::Filter::contains
+/// [`seed`]: BinaryFuse::seed
+/// [`segment_length`]: BinaryFuse::segment_length
+/// [`segment_length_mask`]: BinaryFuse::segment_length_mask
+/// [`segment_count_length`]: BinaryFuse::segment_count_length
+#[inline]
+pub const fn bfuse8_contains(
+    key: u64,
+    seed: u64,
+    segment_length: u32,
+    segment_length_mask: u32,
+    segment_count_length: u32,
+    fingerprints: &[u8],
+) -> bool {
+    if fingerprints.is_empty() {
+        return false;
     }
+    let hash = mix(key, seed);
+    let f = crate::fingerprint!(hash) as u8;
+    let (h0, h1, h2) = hash_of_hash(
+        hash,
+        segment_length,
+        segment_length_mask,
+        segment_count_length,
+    );
+    f == fingerprints[h0 as usize] ^ fingerprints[h1 as usize] ^ fingerprints[h2 as usize]
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{BinaryFuse8, Filter};
+    use crate::{BinaryFuse8, BinaryFuse8Builder, FillRng, Filter, SplitMix64};
     use core::convert::TryFrom;
 
     use alloc::vec::Vec;
@@ -149,11 +361,58 @@ mod test {
         let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
 
         let filter = BinaryFuse8::try_from(&keys).unwrap();
-        let bpe = (filter.len() as f64) * 8.0 / (SAMPLE_SIZE as f64);
+        let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 
         assert!(bpe < 9.1, "Bits per entry is {}", bpe);
     }
 
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_initialization_parallel() {
+        use rayon::prelude::*;
+
+        const SAMPLE_SIZE: usize = 1_000_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse8::try_from_iterator_parallel(keys.par_iter().copied()).unwrap();
+        let serial = BinaryFuse8::try_from(&keys).unwrap();
+        assert_eq!(filter.seed, serial.seed);
+        assert_eq!(filter.fingerprints, serial.fingerprints);
+
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_try_from_checked() {
+        let keys: Vec<u64> = (0..1_000).collect();
+        let filter = BinaryFuse8::try_from_checked(&keys).unwrap();
+        for key in &keys {
+            assert!(filter.contains(key));
+        }
+
+        let duplicated: Vec<u64> = keys.iter().chain(&keys[..10]).copied().collect();
+        assert_eq!(
+            BinaryFuse8::try_from_checked(&duplicated).unwrap_err(),
+            crate::BuildError::DuplicateKeys
+        );
+    }
+
+    #[test]
+    fn test_try_from_verified() {
+        let keys: Vec<u64> = (0..100_000).collect();
+        let filter = BinaryFuse8::try_from_verified(&keys).unwrap();
+        assert_eq!(filter.seed(), BinaryFuse8::try_from(&keys).unwrap().seed());
+        for key in &keys {
+            assert!(filter.contains(key));
+        }
+
+        let filter = BinaryFuse8::try_from_verified(&[]).unwrap();
+        assert!(filter.is_empty());
+    }
+
     #[test]
     fn test_false_positives() {
         const SAMPLE_SIZE: usize = 1_000_000;
@@ -170,6 +429,390 @@ mod test {
         assert!(fp_rate < 0.406, "False positive rate is {}", fp_rate);
     }
 
+    #[test]
+    fn test_false_positive_rate() {
+        const SAMPLE_SIZE: usize = 1_000_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+
+        let false_positives: usize = (0..SAMPLE_SIZE)
+            .map(|_| rng.gen())
+            .filter(|n| filter.contains(n))
+            .count();
+        let fp_rate = false_positives as f64 / SAMPLE_SIZE as f64;
+        assert!(
+            fp_rate < filter.false_positive_rate() * 1.1,
+            "False positive rate is {}",
+            fp_rate
+        );
+    }
+
+    #[test]
+    fn test_contains_many() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+
+        let mut out = vec![false; SAMPLE_SIZE];
+        filter.contains_many(&keys, &mut out);
+        assert!(out.iter().all(|&found| found));
+
+        let queries: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        filter.contains_many(&queries, &mut out);
+        for (key, found) in queries.iter().zip(out) {
+            assert_eq!(filter.contains(key), found);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "simd")]
+    fn test_contains_simd() {
+        // Not a multiple of the lane count, so the scalar tail is exercised too.
+        const SAMPLE_SIZE: usize = 100_003;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+
+        assert!(filter.contains_simd(&keys).iter().all(|&found| found));
+
+        let queries: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        let found = filter.contains_simd(&queries);
+        assert_eq!(found.len(), SAMPLE_SIZE);
+        for (key, found) in queries.iter().zip(found) {
+            assert_eq!(filter.contains(key), found);
+        }
+    }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+        let bytes = filter.to_bytes();
+        let deserialized = BinaryFuse8::from_bytes(&bytes).unwrap();
+
+        assert_eq!(filter.seed, deserialized.seed);
+        assert_eq!(filter.segment_length, deserialized.segment_length);
+        assert_eq!(
+            filter.segment_count_length,
+            deserialized.segment_count_length
+        );
+        assert_eq!(filter.fingerprints, deserialized.fingerprints);
+        assert_eq!(filter, deserialized);
+
+        assert!(BinaryFuse8::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(crate::BinaryFuse16::from_bytes(&bytes).is_err());
+        assert!(crate::Xor8::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_empty_slots_randomized() {
+        let keys: Vec<u64> = (0..10_000).collect();
+
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+        assert_eq!(
+            filter.empty_slots_randomized(),
+            cfg!(feature = "uniform-random")
+        );
+        let filter =
+            BinaryFuse8::try_from_iterator_with_rng(keys.iter().copied(), &mut SplitMix64::new(1))
+                .unwrap();
+        assert!(filter.empty_slots_randomized());
+        assert!(BinaryFuse8::from_bytes(&filter.to_bytes())
+            .unwrap()
+            .empty_slots_randomized());
+    }
+
+    #[test]
+    fn test_from_earlier_version_bytes() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter =
+            BinaryFuse8::try_from_iterator_with_rng(keys.iter().copied(), &mut SplitMix64::new(1))
+                .unwrap();
+
+        // Version 2 bytes have no flags byte after the key count.
+        let mut bytes = filter.to_bytes();
+        assert_eq!(&bytes[..7], b"XORF\x03\x01\x08");
+        bytes[4] = 2;
+        bytes.remove(35);
+
+        let deserialized = BinaryFuse8::from_bytes(&bytes).unwrap();
+        assert!(!deserialized.empty_slots_randomized());
+        assert_eq!(deserialized.key_count(), 10_000);
+        assert_eq!(filter.fingerprints, deserialized.fingerprints);
+
+        // Version 1 bytes have no key count after the parameters either.
+        bytes[4] = 1;
+        bytes.drain(27..35);
+
+        let deserialized = BinaryFuse8::from_bytes(&bytes).unwrap();
+        assert_eq!(deserialized.key_count(), filter.estimated_capacity());
+        assert_eq!(filter.fingerprints, deserialized.fingerprints);
+        assert!(keys.iter().all(|key| deserialized.contains(key)));
+
+        bytes[4] = 4;
+        assert!(BinaryFuse8::from_bytes(&bytes).is_err());
+    }
+
+    #[cfg(feature = "bincode")]
+    #[test]
+    fn test_bincode_from_earlier_version() {
+        // A `BinaryFuse8` of these keys encoded with `bincode::config::standard()` by a version of
+        // this crate from before filters recorded their key count and empty slot randomization.
+        const BYTES: [u8; 61] = [
+            253, 193, 92, 2, 137, 236, 45, 10, 145, 16, 15, 16, 48, 43, 84, 101, 25, 150, 121, 170,
+            113, 178, 194, 114, 159, 102, 42, 1, 110, 66, 243, 253, 47, 198, 70, 129, 187, 97, 200,
+            10, 81, 239, 60, 87, 9, 184, 241, 119, 109, 218, 9, 151, 7, 215, 0, 44, 94, 234, 153,
+            38, 156,
+        ];
+        let keys: Vec<u64> = (0..10u64)
+            .map(|key| key.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .collect();
+
+        let config = bincode::config::standard();
+        let (filter, len): (BinaryFuse8, usize) =
+            bincode::decode_from_slice(&BYTES, config).unwrap();
+        assert_eq!(len, BYTES.len());
+        assert!(keys.iter().all(|key| filter.contains(key)));
+        assert!(!filter.empty_slots_randomized());
+        assert_eq!(filter.key_count(), filter.estimated_capacity());
+        assert_eq!(bincode::encode_to_vec(&filter, config).unwrap(), BYTES);
+
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+        let bytes = bincode::encode_to_vec(&filter, config).unwrap();
+        let (deserialized, _): (BinaryFuse8, usize) =
+            bincode::borrow_decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(filter.fingerprints, deserialized.fingerprints);
+        assert!(keys.iter().all(|key| deserialized.contains(key)));
+    }
+
+    #[test]
+    fn test_from_go_bytes() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&filter.seed.to_le_bytes());
+        bytes.extend_from_slice(&filter.segment_length.to_le_bytes());
+        bytes.extend_from_slice(&filter.segment_length_mask.to_le_bytes());
+        bytes.extend_from_slice(&filter.segment_count_length.to_le_bytes());
+        bytes.extend_from_slice(&filter.fingerprints);
+
+        let deserialized = BinaryFuse8::from_go_bytes(&bytes).unwrap();
+        assert_eq!(filter.fingerprints, deserialized.fingerprints);
+        assert!(!deserialized.empty_slots_randomized());
+        for key in keys {
+            assert!(deserialized.contains(&key));
+        }
+
+        assert!(BinaryFuse8::from_go_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(BinaryFuse8::from_go_bytes(&bytes[..12]).is_err());
+    }
+
+    #[test]
+    fn test_builder() {
+        let keys: Vec<u64> = (0..10_000).collect();
+
+        let built = BinaryFuse8Builder::new()
+            .seed(42)
+            .build(keys.iter().copied())
+            .unwrap();
+        let seeded = BinaryFuse8::try_from_iterator_with_seed(keys.iter().copied(), 42).unwrap();
+        assert_eq!(built.seed, seeded.seed);
+        for key in &keys {
+            assert!(built.contains(key));
+        }
+
+        let mut builder = BinaryFuse8Builder::new().max_iterations(0);
+        assert_eq!(
+            builder.build(keys.iter().copied()).unwrap_err(),
+            crate::BuildError::ConstructionFailed
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "uniform-random")]
+    fn test_randomized_slots_are_reproducible() {
+        let keys: Vec<u64> = (0..10_000).collect();
+
+        let a = BinaryFuse8::try_from(&keys).unwrap();
+        let b = BinaryFuse8::try_from(&keys).unwrap();
+        assert_eq!(a, b);
+
+        // Unused slots are random rather than zero, so only about 1 in 256 fingerprints is zero.
+        let zeroes = a.fingerprints.iter().filter(|&&fp| fp == 0).count();
+        assert!(zeroes < a.len() / 100, "{} zero fingerprints", zeroes);
+    }
+
+    #[test]
+    fn test_builder_without_randomized_slots() {
+        let keys: Vec<u64> = (0..10_000).collect();
+
+        let mut builder = BinaryFuse8Builder::new().randomize_empty_slots(false);
+        let a = builder.build(keys.iter().copied()).unwrap();
+        let b = builder.build(keys.iter().copied()).unwrap();
+        assert_eq!(a.fingerprints, b.fingerprints);
+    }
+
+    #[test]
+    fn test_builder_with_randomized_slots() {
+        let keys: Vec<u64> = (0..10_000).collect();
+
+        let mut builder = BinaryFuse8Builder::new().randomize_empty_slots(true);
+        let a = builder.build(keys.iter().copied()).unwrap();
+        assert!(a.empty_slots_randomized());
+        assert_eq!(a, builder.build(keys.iter().copied()).unwrap());
+
+        let zeroes = a.fingerprints.iter().filter(|&&fp| fp == 0).count();
+        assert!(zeroes < a.len() / 100, "{} zero fingerprints", zeroes);
+    }
+
+    #[test]
+    fn test_eq() {
+        let keys: Vec<u64> = (0..10_000).collect();
+
+        let mut builder = BinaryFuse8Builder::new().randomize_empty_slots(false);
+        let a = builder.build(keys.iter().copied()).unwrap();
+        let b = builder.build(keys.iter().copied()).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, BinaryFuse8::from_bytes(&a.to_bytes()).unwrap());
+
+        let c = builder.build(keys.iter().copied().skip(1)).unwrap();
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_construction_with_rng() {
+        struct Counter(u64);
+
+        impl FillRng for Counter {
+            fn next_u64(&mut self) -> u64 {
+                self.0 += 1;
+                self.0
+            }
+        }
+
+        let keys: Vec<u64> = (0..10_000).collect();
+
+        let a =
+            BinaryFuse8::try_from_iterator_with_rng(keys.iter().copied(), &mut Counter(0)).unwrap();
+        let b = BinaryFuse8Builder::new()
+            .build_with_rng(keys.iter().copied(), &mut Counter(0))
+            .unwrap();
+        assert_eq!(a.fingerprints, b.fingerprints);
+        for key in &keys {
+            assert!(a.contains(key));
+        }
+    }
+
+    #[test]
+    fn test_builder_max_segment_length() {
+        let keys: Vec<u64> = (0..100_000).collect();
+        let default = BinaryFuse8::try_from(&keys).unwrap();
+        assert_eq!(default.segment_length(), 2048);
+
+        // Rounded down to 1024.
+        let filter = BinaryFuse8Builder::new()
+            .max_segment_length(1_500)
+            .build(keys.iter().copied())
+            .unwrap();
+        assert_eq!(filter.segment_length(), 1024);
+        assert!(filter.len() < default.len());
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_builder_reuse() {
+        let mut builder = BinaryFuse8Builder::new();
+        for size in [10_000, 100, 50_000, 0, 1_000] {
+            let keys: Vec<u64> = (0..size).map(|i| i * 7).collect();
+
+            let reused = builder
+                .build_with_rng(keys.iter().copied(), &mut SplitMix64::new(size))
+                .unwrap();
+            let one_shot = BinaryFuse8::try_from_iterator_with_rng(
+                keys.iter().copied(),
+                &mut SplitMix64::new(size),
+            )
+            .unwrap();
+            assert_eq!(reused.seed, one_shot.seed);
+            assert_eq!(reused.fingerprints, one_shot.fingerprints);
+        }
+    }
+
+    #[test]
+    fn test_construction_with_seed() {
+        let keys: Vec<u64> = (0..1_000).collect();
+
+        let a = BinaryFuse8::try_from_iterator_with_seed(keys.iter().copied(), 42).unwrap();
+        let b = BinaryFuse8::try_from_iterator_with_seed(keys.iter().copied(), 42).unwrap();
+        assert_eq!(a, b);
+
+        let c = BinaryFuse8::try_from_iterator_with_seed(keys.iter().copied(), 43).unwrap();
+        assert_ne!(a.seed, c.seed);
+        for key in keys {
+            assert!(c.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_construction_with_stats() {
+        let keys: Vec<u64> = (0..100_000).collect();
+
+        let (filter, stats) =
+            BinaryFuse8::try_from_iterator_with_stats(keys.iter().copied()).unwrap();
+        assert!(stats.attempts >= 1);
+        assert_eq!(stats.duplicates, 0);
+        assert_eq!(stats.final_seed, filter.seed);
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+
+        let (_, stats) = BinaryFuse8::try_from_iterator_with_stats(core::iter::empty()).unwrap();
+        assert_eq!(stats, crate::BuildStats::default());
+    }
+
+    #[test]
+    fn test_construction_with_progress() {
+        let keys: Vec<u64> = (0..100_000).collect();
+
+        let mut attempts = Vec::new();
+        let filter = BinaryFuse8::try_from_iterator_with_progress(keys.iter().copied(), |n| {
+            attempts.push(n)
+        })
+        .unwrap();
+        assert!(!attempts.is_empty());
+        assert!(attempts.iter().copied().eq(1..=attempts.len()));
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn test_stats_count_duplicates() {
+        let mut keys: Vec<u64> = (0..10_000).collect();
+        keys.extend(0..10);
+
+        let (filter, stats) =
+            BinaryFuse8::try_from_iterator_with_stats(keys.iter().copied()).unwrap();
+        assert_eq!(stats.duplicates, 10);
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
     #[test]
     #[cfg(debug_assertions)]
     #[should_panic(
@@ -179,10 +822,346 @@ mod test {
         let _ = BinaryFuse8::try_from(vec![1, 2, 1]);
     }
 
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_fail_construction_with_too_many_keys() {
+        // Reports more keys than can be indexed without ever producing them.
+        #[derive(Clone)]
+        struct ManyKeys;
+
+        impl Iterator for ManyKeys {
+            type Item = u64;
+
+            fn next(&mut self) -> Option<u64> {
+                None
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                const SIZE: usize = 5_000_000_000;
+                (SIZE, Some(SIZE))
+            }
+        }
+
+        impl ExactSizeIterator for ManyKeys {}
+
+        let filter = BinaryFuse8::try_from_iterator(ManyKeys);
+        assert!(filter.expect_err("") == crate::BuildError::TooManyKeys);
+    }
+
     #[test]
     fn test_build_failure_with_subtraction_overflow() {
         let key = rand::random();
         let filter = BinaryFuse8::try_from(vec![key]).unwrap();
         assert!(filter.contains(&key));
     }
+
+    #[test]
+    fn test_adversarial_keys_sharing_slots() {
+        // Inverts `murmur3::mix64`, so that keys can be chosen to hash to given values.
+        fn unmix64(mut k: u64) -> u64 {
+            let inverse = |a: u64| {
+                let mut x = a;
+                for _ in 0..5 {
+                    x = x.wrapping_mul(2u64.wrapping_sub(a.wrapping_mul(x)));
+                }
+                x
+            };
+            k ^= k >> 33;
+            k = k.wrapping_mul(inverse(0xc4ce_b9fe_1a85_ec53));
+            k ^= k >> 33;
+            k = k.wrapping_mul(inverse(0xff51_afd7_ed55_8ccd));
+            k ^ (k >> 33)
+        }
+
+        // The first seed a default build tries.
+        let seed = crate::splitmix64::splitmix64(&mut 1);
+        // Hashes that differ only in bits no index is derived from, so that under `seed` all of
+        // these keys land in the same three slots, more than a slot can count.
+        let mut keys: Vec<u64> = (1..=100u64)
+            .map(|i| unmix64(i << 40).wrapping_sub(seed))
+            .collect();
+        keys.extend(1_000_000..1_010_000);
+        assert_eq!(crate::prelude::mix(keys[0], seed), 1 << 40);
+
+        let (filter, stats) =
+            BinaryFuse8::try_from_iterator_with_stats(keys.iter().copied()).unwrap();
+        assert!(stats.attempts > 1);
+        assert_ne!(stats.final_seed, seed);
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_sorted_iterator() {
+        let keys: Vec<u64> = (0..100_000).map(|key| key * 3).collect();
+        let filter = BinaryFuse8::try_from_sorted_iterator(keys.iter().copied()).unwrap();
+        for key in keys.iter() {
+            assert!(filter.contains(key));
+        }
+        assert_eq!(filter, BinaryFuse8::try_from(&keys).unwrap());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "Keys must be sorted in ascending order without duplicates.")]
+    fn test_debug_assert_sorted() {
+        let _ = BinaryFuse8::try_from_sorted_iterator([1, 3, 2].iter().copied());
+    }
+
+    #[test]
+    fn test_single_pass_iterator() {
+        let mut next = 0;
+        let keys = core::iter::from_fn(|| {
+            next += 1;
+            (next <= 10_000).then(|| next * 7)
+        });
+
+        let filter = BinaryFuse8::try_from_single_pass_iterator(keys).unwrap();
+        for key in (1..=10_000).map(|key| key * 7) {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_len_for() {
+        for size in [0, 1, 2, 10, 1_000, 100_000, 1_000_000] {
+            let keys: Vec<u64> = (0..size).collect();
+            let filter = BinaryFuse8::try_from(&keys).unwrap();
+            assert_eq!(
+                BinaryFuse8::fingerprint_len_for(keys.len()),
+                Ok(filter.len()),
+                "{} keys",
+                size
+            );
+        }
+        assert_eq!(
+            BinaryFuse8::fingerprint_len_for(u32::MAX as usize),
+            Err(crate::BuildError::TooManyKeys)
+        );
+    }
+
+    #[test]
+    fn test_descriptor() {
+        use crate::Descriptor;
+        use core::mem::{align_of, offset_of, size_of};
+
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+        let descriptor = filter.descriptor();
+        assert_eq!(descriptor.seed, filter.seed());
+        assert_eq!(descriptor.segment_length, filter.segment_length());
+        assert_eq!(descriptor.segment_length_mask, filter.segment_length_mask());
+        assert_eq!(
+            descriptor.segment_count_length,
+            filter.segment_count_length()
+        );
+        assert_eq!(BinaryFuse8::default().descriptor(), Descriptor::default());
+
+        // The layout of the matching C struct.
+        assert_eq!(offset_of!(Descriptor, seed), 0);
+        assert_eq!(offset_of!(Descriptor, segment_length), 8);
+        assert_eq!(offset_of!(Descriptor, segment_length_mask), 12);
+        assert_eq!(offset_of!(Descriptor, segment_count_length), 16);
+        assert_eq!(size_of::<Descriptor>(), 24);
+        assert_eq!(align_of::<Descriptor>(), 8);
+    }
+
+    #[test]
+    fn test_estimated_capacity() {
+        for size in [1_000, 10_000, 100_000, 1_000_000] {
+            let keys: Vec<u64> = (0..size).collect();
+            let filter = BinaryFuse8::try_from(&keys).unwrap();
+            let estimate = filter.estimated_capacity() as f64;
+            let error = (estimate - size as f64).abs() / size as f64;
+            assert!(error < 0.03, "Estimated {} keys for {}", estimate, size);
+        }
+        assert_eq!(BinaryFuse8::default().estimated_capacity(), 0);
+    }
+
+    #[test]
+    fn test_key_count() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+        assert_eq!(filter.key_count(), 10_000);
+        assert_eq!(
+            BinaryFuse8::from_bytes(&filter.to_bytes())
+                .unwrap()
+                .key_count(),
+            10_000
+        );
+        assert_eq!(filter.truncate_fingerprints::<u8>().key_count(), 10_000);
+        assert_eq!(BinaryFuse8::default().key_count(), 0);
+    }
+
+    #[test]
+    fn test_slack_ratio() {
+        for size in [1_000, 100_000, 1_000_000] {
+            let keys: Vec<u64> = (0..size).collect();
+            let filter = BinaryFuse8::try_from(&keys).unwrap();
+            let slack = 1.0 - size as f64 / filter.len() as f64;
+            let estimate = filter.slack_ratio();
+            assert!(
+                (estimate - slack).abs() < 0.03,
+                "Estimated slack {} for {}",
+                estimate,
+                slack
+            );
+            assert!(estimate > 0.1 && estimate < 0.5);
+        }
+        assert_eq!(BinaryFuse8::default().slack_ratio(), 0.0);
+    }
+
+    #[test]
+    fn test_union() {
+        let a: Vec<u64> = (0..10_000).collect();
+        let b: Vec<u64> = (5_000..15_000).chain(5_000..6_000).collect();
+
+        let filter = BinaryFuse8::try_from_union(&a, &b).unwrap();
+        for key in a.iter().chain(b.iter()) {
+            assert!(filter.contains(key));
+        }
+
+        let union: Vec<u64> = (0..15_000).collect();
+        let expected = BinaryFuse8::try_from(&union).unwrap();
+        assert_eq!(filter.seed(), expected.seed());
+        assert_eq!(filter.fingerprints, expected.fingerprints);
+    }
+
+    #[test]
+    fn test_params() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+
+        assert!(filter.segment_length().is_power_of_two());
+        assert_eq!(filter.segment_length_mask(), filter.segment_length() - 1);
+        assert_eq!(
+            filter.fingerprints.len(),
+            (filter.segment_count_length() + 2 * filter.segment_length()) as usize
+        );
+
+        let deserialized = BinaryFuse8::from_bytes(&filter.to_bytes()).unwrap();
+        assert_eq!(deserialized.seed(), filter.seed());
+    }
+
+    #[test]
+    fn test_display() {
+        let keys: Vec<u64> = (0..100_000).collect();
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+        let summary = format!("{}", filter);
+        assert!(
+            summary.starts_with("BinaryFuse8 { fingerprints: "),
+            "{}",
+            summary
+        );
+        assert!(summary.ends_with(", fp_rate: ~2^-8 }"), "{}", summary);
+    }
+
+    #[test]
+    fn test_contains_mixed() {
+        use crate::hashing::mix;
+
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+
+        for key in keys.iter() {
+            assert!(filter.contains_mixed(mix(*key, filter.seed())));
+        }
+        for key in (0..SAMPLE_SIZE).map(|_| rng.gen()) {
+            assert_eq!(
+                filter.contains_mixed(mix(key, filter.seed())),
+                filter.contains(&key)
+            );
+        }
+        assert!(!BinaryFuse8::default().contains_mixed(0));
+    }
+
+    #[test]
+    fn test_debug() {
+        let keys: Vec<u64> = (0..1_000_000).collect();
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+        let debug = format!("{:?}", filter);
+        assert!(debug.len() < 200, "{}", debug);
+        assert!(
+            debug.ends_with(&format!(".. len {} ..] }}", filter.len())),
+            "{}",
+            debug
+        );
+    }
+
+    #[test]
+    fn test_const_contains() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+
+        let contains = |key: u64| {
+            crate::bfuse8_contains(
+                key,
+                filter.seed(),
+                filter.segment_length(),
+                filter.segment_length_mask(),
+                filter.segment_count_length(),
+                &filter.fingerprints,
+            )
+        };
+        for key in keys {
+            assert!(contains(key));
+        }
+        for key in (0..SAMPLE_SIZE).map(|_| rng.gen()) {
+            assert_eq!(contains(key), filter.contains(&key));
+        }
+
+        const _: () = assert!(!crate::bfuse8_contains(0, 0, 0, 0, 0, &[]));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stream_roundtrip() {
+        use std::io::ErrorKind;
+
+        let keys: Vec<u64> = (0..100_000).collect();
+        let filter = BinaryFuse8::try_from(&keys).unwrap();
+
+        let mut bytes = Vec::new();
+        filter.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes, filter.to_bytes());
+
+        // Anything after the filter is left in the stream.
+        bytes.extend_from_slice(b"tail");
+        let mut stream = bytes.as_slice();
+        assert_eq!(BinaryFuse8::read_from(&mut stream).unwrap(), filter);
+        assert_eq!(stream, b"tail");
+
+        let truncated = &filter.to_bytes()[..1_000];
+        let err = BinaryFuse8::read_from(&mut &truncated[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        let err = crate::BinaryFuse16::read_from(&mut &bytes[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_from_array() {
+        let filter = BinaryFuse8::try_from(&[1, 2, 3]).unwrap();
+        assert!([1, 2, 3].iter().all(|key| filter.contains(key)));
+        assert_eq!(filter, BinaryFuse8::try_from(&[1, 2, 3][..]).unwrap());
+    }
+
+    #[test]
+    fn test_empty() {
+        let filter = BinaryFuse8::try_from(Vec::new()).unwrap();
+        assert!(filter.is_empty());
+        assert!((0..1_000).all(|key| !filter.contains(&key)));
+
+        let mut out = [true; 1_000];
+        filter.contains_many(&(0..1_000).collect::<Vec<_>>(), &mut out);
+        assert!(out.iter().all(|&found| !found));
+
+        let deserialized = BinaryFuse8::from_bytes(&filter.to_bytes()).unwrap();
+        assert!(deserialized.is_empty());
+        assert!(!deserialized.contains(&0));
+    }
 }
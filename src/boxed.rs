@@ -0,0 +1,141 @@
+//! Implements construction of filters whose type is selected at runtime.
+
+use crate::{BuildError, Filter, Xor16, Xor32, Xor64, Xor8};
+use alloc::boxed::Box;
+
+#[cfg(feature = "binary-fuse")]
+use crate::{BinaryFuse16, BinaryFuse24, BinaryFuse32, BinaryFuse64, BinaryFuse8};
+#[cfg(feature = "binary-fuse")]
+use core::convert::TryFrom;
+
+/// A type of filter [`build_boxed`] can construct.
+///
+/// The Binary Fuse kinds are only available with the `binary-fuse` feature, and more kinds may be
+/// added in future versions, so the enum is non-exhaustive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FilterKind {
+    /// An [`Xor8`] filter.
+    Xor8,
+    /// An [`Xor16`] filter.
+    Xor16,
+    /// An [`Xor32`] filter.
+    Xor32,
+    /// An [`Xor64`] filter.
+    Xor64,
+    /// A [`BinaryFuse8`] filter.
+    #[cfg(feature = "binary-fuse")]
+    BinaryFuse8,
+    /// A [`BinaryFuse16`] filter.
+    #[cfg(feature = "binary-fuse")]
+    BinaryFuse16,
+    /// A [`BinaryFuse24`] filter.
+    #[cfg(feature = "binary-fuse")]
+    BinaryFuse24,
+    /// A [`BinaryFuse32`] filter.
+    #[cfg(feature = "binary-fuse")]
+    BinaryFuse32,
+    /// A [`BinaryFuse64`] filter.
+    #[cfg(feature = "binary-fuse")]
+    BinaryFuse64,
+}
+
+/// Constructs a filter of type `kind` from `keys`, boxed behind the [`Filter`] trait.
+///
+/// This allows the type of a filter to be selected at runtime, such as from configuration, and
+/// filters of different types to be stored together. Returns an error, rather than panicking, if
+/// the filter fails to be constructed, such as from keys that contain duplicates.
+///
+/// ```
+/// # extern crate alloc;
+/// use xorf::{build_boxed, Filter, FilterKind};
+/// # use alloc::{boxed::Box, vec::Vec};
+///
+/// let keys: Vec<u64> = (0..10_000).collect();
+/// let filters: Vec<Box<dyn Filter<u64>>> = [FilterKind::Xor8, FilterKind::Xor16]
+///     .iter()
+///     .map(|&kind| build_boxed(kind, &keys).unwrap())
+///     .collect();
+///
+/// for filter in filters.iter() {
+///     assert!(keys.iter().all(|key| filter.contains(key)));
+/// }
+/// ```
+pub fn build_boxed(kind: FilterKind, keys: &[u64]) -> Result<Box<dyn Filter<u64>>, BuildError> {
+    Ok(match kind {
+        FilterKind::Xor8 => Box::new(Xor8::try_from_iterator(keys.iter().copied())?),
+        FilterKind::Xor16 => Box::new(Xor16::try_from_iterator(keys.iter().copied())?),
+        FilterKind::Xor32 => Box::new(Xor32::try_from_iterator(keys.iter().copied())?),
+        FilterKind::Xor64 => Box::new(Xor64::try_from_iterator(keys.iter().copied())?),
+        #[cfg(feature = "binary-fuse")]
+        FilterKind::BinaryFuse8 => Box::new(BinaryFuse8::try_from(keys)?),
+        #[cfg(feature = "binary-fuse")]
+        FilterKind::BinaryFuse16 => Box::new(BinaryFuse16::try_from(keys)?),
+        #[cfg(feature = "binary-fuse")]
+        FilterKind::BinaryFuse24 => Box::new(BinaryFuse24::try_from(keys)?),
+        #[cfg(feature = "binary-fuse")]
+        FilterKind::BinaryFuse32 => Box::new(BinaryFuse32::try_from(keys)?),
+        #[cfg(feature = "binary-fuse")]
+        FilterKind::BinaryFuse64 => Box::new(BinaryFuse64::try_from(keys)?),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{build_boxed, FilterKind};
+
+    use alloc::vec::Vec;
+    use rand::Rng;
+
+    const KINDS: &[FilterKind] = &[
+        FilterKind::Xor8,
+        FilterKind::Xor16,
+        FilterKind::Xor32,
+        FilterKind::Xor64,
+        #[cfg(feature = "binary-fuse")]
+        FilterKind::BinaryFuse8,
+        #[cfg(feature = "binary-fuse")]
+        FilterKind::BinaryFuse16,
+        #[cfg(feature = "binary-fuse")]
+        FilterKind::BinaryFuse24,
+        #[cfg(feature = "binary-fuse")]
+        FilterKind::BinaryFuse32,
+        #[cfg(feature = "binary-fuse")]
+        FilterKind::BinaryFuse64,
+    ];
+
+    #[test]
+    fn test_build_boxed() {
+        const SAMPLE_SIZE: usize = 10_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        for &kind in KINDS {
+            let filter = build_boxed(kind, &keys).unwrap();
+            for key in keys.iter() {
+                assert!(filter.contains(key), "{:?}", kind);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn test_build_boxed_with_duplicates() {
+        use crate::BuildError;
+
+        let keys = [1, 2, 1];
+        for kind in [
+            FilterKind::Xor8,
+            FilterKind::Xor16,
+            FilterKind::Xor32,
+            FilterKind::Xor64,
+        ] {
+            assert_eq!(
+                build_boxed(kind, &keys).err(),
+                Some(BuildError::ConstructionFailed),
+                "{:?}",
+                kind
+            );
+        }
+    }
+}
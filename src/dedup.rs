@@ -0,0 +1,55 @@
+//! Implements helpers for de-duplicating keys before constructing a filter.
+
+use alloc::{collections::BTreeSet, vec::Vec};
+
+/// Sorts `keys` and removes duplicate keys in place.
+///
+/// ```
+/// let mut keys = vec![3, 1, 3, 2, 1];
+/// xorf::dedup_sorted(&mut keys);
+/// assert_eq!(keys, [1, 2, 3]);
+/// ```
+pub fn dedup_sorted(keys: &mut Vec<u64>) {
+    keys.sort_unstable();
+    keys.dedup();
+}
+
+/// Returns the distinct keys of `keys`, in ascending order.
+///
+/// ```
+/// use xorf::{Filter, Xor8};
+///
+/// let raw = [3, 1, 3, 2, 1];
+/// let filter = Xor8::from(&xorf::dedup(&raw));
+/// assert!(raw.iter().all(|key| filter.contains(key)));
+/// ```
+pub fn dedup(keys: &[u64]) -> Vec<u64> {
+    keys.iter()
+        .copied()
+        .collect::<BTreeSet<u64>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{dedup, dedup_sorted};
+
+    use alloc::vec::Vec;
+    use rand::Rng;
+
+    #[test]
+    fn test_dedup() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen_range(0..1_000)).collect();
+
+        let distinct = dedup(&keys);
+        assert!(distinct.windows(2).all(|w| w[0] < w[1]));
+        assert!(keys.iter().all(|key| distinct.binary_search(key).is_ok()));
+
+        let mut sorted = keys;
+        dedup_sorted(&mut sorted);
+        assert_eq!(sorted, distinct);
+    }
+}
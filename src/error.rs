@@ -0,0 +1,42 @@
+//! Implements the error returned when a filter fails to be constructed.
+
+use core::fmt;
+
+/// The reason a filter failed to be constructed from a set of keys.
+///
+/// ```
+/// use xorf::{BuildError, Xor8};
+///
+/// // Give up before the first construction attempt.
+/// let err = Xor8::try_from_iterator_with_max_iterations([1, 2, 3].into_iter(), 0).unwrap_err();
+/// assert_eq!(err, BuildError::ConstructionFailed);
+/// assert_eq!(err.to_string(), "Failed to construct filter.");
+/// ```
+///
+/// More reasons may be added in future versions, so the enum is non-exhaustive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BuildError {
+    /// No attempt at placing the keys in the filter succeeded. This is almost always because
+    /// the keys contain duplicates.
+    ConstructionFailed,
+    /// The keys contain duplicates, and the constructor checks for them.
+    DuplicateKeys,
+    /// There are more keys than the filter can index.
+    TooManyKeys,
+    /// The constructed filter does not contain one of the keys it was constructed from.
+    VerificationFailed,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::ConstructionFailed => "Failed to construct filter.",
+            Self::DuplicateKeys => "Filters must be constructed from distinct keys.",
+            Self::TooManyKeys => "Too many keys to construct filter.",
+            Self::VerificationFailed => "Filter verification failed.",
+        })
+    }
+}
+
+impl core::error::Error for BuildError {}
@@ -0,0 +1,95 @@
+//! Implements random number generators for filling unused fingerprint slots.
+
+use crate::splitmix64::splitmix64;
+
+/// A random number generator used to fill the unused fingerprint slots of a filter.
+///
+/// Filling unused slots with random values, rather than zeroes, makes a filter's false positive
+/// rate uniform across keys. `FillRng` is a minimal interface so that `no_std` users can supply
+/// their own generator, or use the bundled [`SplitMix64`], which needs neither `std` nor `rand`.
+/// With the `rand` feature, it is implemented for every [`rand::RngCore`].
+///
+/// Filters constructed without a `FillRng` fill unused slots with zeroes, unless the
+/// `uniform-random` feature is enabled.
+///
+/// [`rand::RngCore`]: https://docs.rs/rand/0.8/rand/trait.RngCore.html
+pub trait FillRng {
+    /// Returns the next random value.
+    fn next_u64(&mut self) -> u64;
+}
+
+#[cfg(feature = "rand")]
+impl<R: rand::RngCore + ?Sized> FillRng for R {
+    fn next_u64(&mut self) -> u64 {
+        rand::RngCore::next_u64(self)
+    }
+}
+
+/// A small, fast [`FillRng`] using Sebastiano Vigna's [SplitMix64] algorithm, for targets without
+/// `std` or `rand`. It is not cryptographically secure, which filling unused slots does not need.
+///
+/// ```
+/// use xorf::{FillRng, SplitMix64};
+///
+/// let mut a = SplitMix64::new(42);
+/// let mut b = SplitMix64::new(42);
+/// assert_eq!(a.next_u64(), b.next_u64());
+/// ```
+///
+/// With the `binary-fuse` feature, it can fill the unused slots of a Binary Fuse filter:
+///
+/// ```
+/// # #[cfg(feature = "binary-fuse")] {
+/// # extern crate alloc;
+/// use xorf::{BinaryFuse8, Filter, SplitMix64};
+/// # use alloc::vec::Vec;
+///
+/// let keys: Vec<u64> = (0..10_000).collect();
+/// let mut rng = SplitMix64::new(42);
+/// let filter = BinaryFuse8::try_from_iterator_with_rng(keys.iter().copied(), &mut rng).unwrap();
+/// assert!(keys.iter().all(|key| filter.contains(key)));
+/// # }
+/// ```
+///
+/// [SplitMix64]: http://xoroshiro.di.unimi.it/splitmix64.c
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    /// Creates a generator from `seed`. Generators created from the same seed produce the same
+    /// values.
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+}
+
+impl FillRng for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        splitmix64(&mut self.state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{FillRng, SplitMix64};
+
+    #[test]
+    fn test_splitmix64() {
+        let mut a = SplitMix64::new(7);
+        let mut b = SplitMix64::new(7);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(SplitMix64::new(7).next_u64(), SplitMix64::new(8).next_u64());
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn test_rand_rng() {
+        let mut rng = rand::rngs::mock::StepRng::new(3, 2);
+        assert_eq!(FillRng::next_u64(&mut rng), 3);
+        assert_eq!(FillRng::next_u64(&mut rng), 5);
+    }
+}
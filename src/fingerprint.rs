@@ -0,0 +1,119 @@
+//! Implements fingerprint types for xor filters.
+
+use alloc::vec::Vec;
+use core::{convert::TryInto, ops::BitXor};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bincode")]
+use bincode::{Decode, Encode};
+
+/// A fingerprint stored in an xor filter.
+///
+/// A key is in a filter if its fingerprint is equal to the xor of the fingerprints stored in the
+/// slots the key hashes to. The false positive rate of a filter is around `2^-BITS`.
+///
+/// `Fingerprint` is implemented for `u8`, `u16`, [`U24`], `u32`, and `u64`.
+pub trait Fingerprint: Copy + Default + Eq + BitXor<Output = Self> + Into<u64> {
+    /// The number of bits in the fingerprint.
+    const BITS: u32;
+
+    /// Derives a fingerprint from the hash of a key.
+    fn from_hash(hash: u64) -> Self;
+
+    /// Appends the little-endian bytes of the fingerprint to `bytes`.
+    fn write_le_bytes(self, bytes: &mut Vec<u8>);
+
+    /// Reads a fingerprint from exactly `BITS / 8` little-endian bytes.
+    fn read_le_bytes(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_fingerprint(
+    ($($fpty:ty => $from_hash:expr),*) => {
+        $(
+            impl Fingerprint for $fpty {
+                const BITS: u32 = <$fpty>::BITS;
+
+                #[inline]
+                fn from_hash(hash: u64) -> Self {
+                    let from_hash: fn(u64) -> $fpty = $from_hash;
+                    from_hash(hash)
+                }
+
+                #[inline]
+                fn write_le_bytes(self, bytes: &mut Vec<u8>) {
+                    bytes.extend_from_slice(&self.to_le_bytes());
+                }
+
+                #[inline]
+                fn read_le_bytes(bytes: &[u8]) -> Self {
+                    <$fpty>::from_le_bytes(bytes.try_into().unwrap())
+                }
+            }
+        )*
+    };
+);
+
+impl_fingerprint!(
+    u8 => |hash| crate::fingerprint!(hash) as u8,
+    u16 => |hash| crate::fingerprint!(hash) as u16,
+    u32 => |hash| crate::fingerprint!(hash) as u32,
+    // The hash is already 64 bits wide, so it is used whole rather than folded.
+    u64 => |hash| hash
+);
+
+/// A 24-bit fingerprint, stored as three little-endian bytes so that it takes up no more space
+/// than it needs.
+///
+/// Used by [`BinaryFuse24`] filters.
+///
+/// [`BinaryFuse24`]: crate::BinaryFuse24
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bincode", derive(Encode, Decode))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct U24([u8; 3]);
+
+impl U24 {
+    /// Returns the fingerprint as an integer.
+    pub const fn get(self) -> u32 {
+        let [b0, b1, b2] = self.0;
+        u32::from_le_bytes([b0, b1, b2, 0])
+    }
+}
+
+impl BitXor for U24 {
+    type Output = Self;
+
+    #[inline]
+    fn bitxor(self, rhs: Self) -> Self {
+        let ([a0, a1, a2], [b0, b1, b2]) = (self.0, rhs.0);
+        Self([a0 ^ b0, a1 ^ b1, a2 ^ b2])
+    }
+}
+
+impl From<U24> for u64 {
+    fn from(fingerprint: U24) -> Self {
+        fingerprint.get().into()
+    }
+}
+
+impl Fingerprint for U24 {
+    const BITS: u32 = 24;
+
+    #[inline]
+    fn from_hash(hash: u64) -> Self {
+        let [b0, b1, b2, ..] = (crate::fingerprint!(hash) as u32).to_le_bytes();
+        Self([b0, b1, b2])
+    }
+
+    #[inline]
+    fn write_le_bytes(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.0);
+    }
+
+    #[inline]
+    fn read_le_bytes(bytes: &[u8]) -> Self {
+        Self(bytes.try_into().unwrap())
+    }
+}
@@ -2,9 +2,13 @@
 
 #![allow(deprecated)] // Fuse32 filters are deprecated, but we need to implement them.
 
-use crate::{fuse_contains_impl, fuse_from_impl, Filter};
+use crate::{
+    fuse_contains_impl, fuse_from_impl,
+    prelude::{fmt_summary, fuse, FingerprintsPreview},
+    BuildError, Filter,
+};
 use alloc::{boxed::Box, vec::Vec};
-use core::convert::TryFrom;
+use core::{convert::TryFrom, fmt};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
@@ -19,9 +23,9 @@ use bincode::{Decode, Encode};
 /// a higher number of entries decreases the bits per entry but increases the false positive rate.
 ///
 /// A `Fuse32` filter uses less space and is faster to construct than an [`Xor32`] filter, but
-/// requires a large number of keys to be constructed. Experimentally, this number is somewhere
-/// >100_000. For smaller key sets, prefer the [`Xor32`] filter. A `Fuse32` filter may fail to be
-/// constructed.
+/// requires a large number of keys to be constructed. Experimentally, this number is
+/// somewhere >100_000. For smaller key sets, prefer the [`Xor32`] filter. A `Fuse32` filter
+/// may fail to be constructed.
 ///
 /// A `Fuse32` is constructed from a set of 64-bit unsigned integers and is immutable.
 ///
@@ -43,7 +47,7 @@ use bincode::{Decode, Encode};
 /// }
 ///
 /// // bits per entry
-/// let bpe = (filter.len() as f64) * 32.0 / (SAMPLE_SIZE as f64);
+/// let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 /// assert!(bpe < 36.404, "Bits per entry is {}", bpe);
 ///
 /// // false positive rate
@@ -55,6 +59,8 @@ use bincode::{Decode, Encode};
 /// assert!(fp_rate < 0.0000000000000001, "False positive rate is {}", fp_rate);
 /// ```
 ///
+/// The default `Fuse32` is empty, the same as a filter constructed from no keys.
+///
 /// Serializing and deserializing `Fuse32` filters can be enabled with the [`serde`] feature  (or [`bincode`] for bincode).
 ///
 /// [fuse graph]: https://arxiv.org/abs/1907.04749
@@ -63,7 +69,7 @@ use bincode::{Decode, Encode};
 #[deprecated(since = "0.8.0", note = "prefer using a `BinaryFuse32`")]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "bincode", derive(Encode, Decode))]
-#[derive(Debug, Clone)]
+#[derive(Clone, Default, PartialEq, Eq)]
 pub struct Fuse32 {
     /// The seed for the filter
     pub seed: u64,
@@ -75,6 +81,7 @@ pub struct Fuse32 {
 
 impl Filter<u64> for Fuse32 {
     /// Returns `true` if the filter contains the specified key.
+    #[inline]
     fn contains(&self, key: &u64) -> bool {
         fuse_contains_impl!(*key, self, fingerprint u32)
     }
@@ -91,24 +98,104 @@ impl Fuse32 {
     /// Note: the iterator will be iterated over multiple times while building
     /// the filter. If using a hash function to map the key, it may be cheaper
     /// just to create a scratch array of hashed keys that you pass in.
-    pub fn try_from_iterator<T>(keys: T) -> Result<Self, &'static str>
+    pub fn try_from_iterator<T>(keys: T) -> Result<Self, BuildError>
     where
         T: ExactSizeIterator<Item = u64> + Clone,
     {
         fuse_from_impl!(keys fingerprint u32, max iter 1_000)
     }
+
+    /// Try to construct the filter from a key iterator, spreading the keys over `segment_count`
+    /// segments instead of the default of 100. Some key sets that fail to be placed with the
+    /// default segment count can be placed with a different one.
+    ///
+    /// Returns an error if `segment_count` is zero.
+    pub fn try_from_iterator_with_segments<T>(
+        keys: T,
+        segment_count: usize,
+    ) -> Result<Self, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        fuse_from_impl!(keys fingerprint u32, max iter 1_000, segments segment_count)
+    }
+
+    /// Returns the theoretical false positive rate of the filter, `2^-32`, averaged over all keys.
+    /// See [unused fingerprint slots] for how it varies between keys.
+    ///
+    /// [unused fingerprint slots]: crate#unused-fingerprint-slots
+    pub const fn false_positive_rate(&self) -> f64 {
+        1.0 / (1u64 << 32) as f64
+    }
+
+    /// Returns the number of bits per entry used by the filter if it was constructed from
+    /// `num_keys` keys. All fingerprint slots are counted, including unused ones.
+    pub fn bits_per_entry_over(&self, num_keys: usize) -> f64 {
+        (self.fingerprints.len() as f64) * 32.0 / (num_keys as f64)
+    }
+
+    /// Returns the number of bytes used by the filter, including its fingerprints.
+    pub fn allocated_bytes(&self) -> usize {
+        core::mem::size_of::<Self>() + core::mem::size_of_val(&*self.fingerprints)
+    }
+
+    /// Returns an iterator over the fingerprints of the filter, each widened to a `u64`. Unlike
+    /// the `fingerprints` field, the item type is the same for every filter.
+    pub fn fingerprints(&self) -> impl Iterator<Item = u64> + '_ {
+        self.fingerprints.iter().map(|&f| u64::from(f))
+    }
+
+    /// Returns an iterator over the fingerprint slots of the filter, yielding the index of each
+    /// slot with its fingerprint widened to a `u64`, for tools that inspect the layout of
+    /// fingerprints across filter types.
+    pub fn slots(&self) -> impl Iterator<Item = (usize, u64)> + '_ {
+        self.fingerprints().enumerate()
+    }
+}
+
+impl fmt::Debug for Fuse32 {
+    /// Formats the filter parameters, but only the first few fingerprints.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Fuse32")
+            .field("seed", &self.seed)
+            .field("segment_length", &self.segment_length)
+            .field("fingerprints", &FingerprintsPreview(&self.fingerprints))
+            .finish()
+    }
+}
+
+impl fmt::Display for Fuse32 {
+    /// Summarizes the filter on one line, with bits per entry computed over an estimate of the
+    /// number of keys the filter was constructed from.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_summary(
+            f,
+            "Fuse",
+            32,
+            self.fingerprints.len(),
+            fuse::estimated_capacity(self.fingerprints.len()),
+        )
+    }
 }
 
 impl TryFrom<&[u64]> for Fuse32 {
-    type Error = &'static str;
+    type Error = BuildError;
 
     fn try_from(keys: &[u64]) -> Result<Self, Self::Error> {
         Self::try_from_iterator(keys.iter().copied())
     }
 }
 
+impl<const N: usize> TryFrom<&[u64; N]> for Fuse32 {
+    type Error = BuildError;
+
+    fn try_from(keys: &[u64; N]) -> Result<Self, Self::Error> {
+        Self::try_from(&keys[..])
+    }
+}
+
 impl TryFrom<&Vec<u64>> for Fuse32 {
-    type Error = &'static str;
+    type Error = BuildError;
 
     fn try_from(v: &Vec<u64>) -> Result<Self, Self::Error> {
         Self::try_from_iterator(v.iter().copied())
@@ -116,7 +203,7 @@ impl TryFrom<&Vec<u64>> for Fuse32 {
 }
 
 impl TryFrom<Vec<u64>> for Fuse32 {
-    type Error = &'static str;
+    type Error = BuildError;
 
     fn try_from(v: Vec<u64>) -> Result<Self, Self::Error> {
         Self::try_from_iterator(v.iter().copied())
@@ -151,7 +238,7 @@ mod test {
         let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
 
         let filter = Fuse32::try_from(&keys).unwrap();
-        let bpe = (filter.len() as f64) * 32.0 / (SAMPLE_SIZE as f64);
+        let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 
         assert!(bpe < 36.404, "Bits per entry is {}", bpe);
     }
@@ -191,6 +278,6 @@ mod test {
         let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
 
         let filter = Fuse32::try_from(&keys);
-        assert!(filter.expect_err("") == "Failed to construct fuse filter.");
+        assert!(filter.expect_err("") == crate::BuildError::ConstructionFailed);
     }
 }
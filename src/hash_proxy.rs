@@ -2,6 +2,8 @@
 
 use crate::Filter;
 use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::convert::TryFrom;
 use core::hash::{Hash, Hasher};
 
 #[cfg(feature = "serde")]
@@ -80,13 +82,36 @@ use bincode::{Decode, Encode};
 /// assert!(fruits.contains(&tangerine_hash)); // doesn't work!
 /// ```
 ///
-/// Serializing and deserializing `HashProxy`s can be enabled with the [`serde`] feature.
+/// To look up precomputed hashes, such as hashes shared by several filters, hash keys with
+/// [`hash_key`] and query the underlying filter directly.
 ///
+/// A `HashProxy` backed by a filter whose construction may fail, like a [`BinaryFuse8`], is
+/// constructed with [`try_from_keys`].
+///
+/// Keys are hashed with an unkeyed hasher, so anyone who knows the hasher can search for keys
+/// whose hashes collide with those in the filter. For keys from untrusted sources, use a
+/// [`KeyedHashProxy`] with a secretly keyed hasher instead.
+///
+/// Serializing and deserializing `HashProxy`s can be enabled with the [`serde`] feature (or
+/// [`bincode`] for bincode). Only the underlying filter is serialized.
+///
+/// [`BinaryFuse8`]: crate::BinaryFuse8
+/// [`KeyedHashProxy`]: crate::KeyedHashProxy
+/// [`try_from_keys`]: HashProxy::try_from_keys
+/// [`hash_key`]: HashProxy::hash_key
 /// [`Filter`]: crate::Filter
 /// [`Hasher`]: core::hash::Hasher
 /// [`serde`]: http://serde.rs
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "bincode", derive(Encode, Decode))]
+#[cfg_attr(
+    feature = "bincode",
+    bincode(
+        encode_bounds = "T: Hash, H: Hasher + Default, F: Filter<u64> + Encode",
+        decode_bounds = "T: Hash, H: Hasher + Default, F: Filter<u64> + Decode<__Context> + 'static",
+        borrow_decode_bounds = "T: Hash, H: Hasher + Default, F: Filter<u64> + bincode::BorrowDecode<'__de, __Context> + '__de"
+    )
+)]
 pub struct HashProxy<T, H, F>
 where
     T: Hash,
@@ -94,12 +119,40 @@ where
     F: Filter<u64>,
 {
     filter: F,
-    _hasher: core::marker::PhantomData<H>,
-    _type: core::marker::PhantomData<T>,
+    // A `HashProxy` holds neither a hasher nor keys, so whether it is `Send` or `Sync` must not
+    // depend on `H` or `T`.
+    _hasher: core::marker::PhantomData<fn() -> H>,
+    _type: core::marker::PhantomData<fn() -> T>,
 }
 
+/// A [`HashProxy`] hashing keys with [`AHasher`], which is considerably faster than
+/// `DefaultHasher` on short keys and is available without `std`.
+///
+/// ```
+/// # extern crate alloc;
+/// use xorf::{FastProxy, Filter, Xor8};
+/// # use alloc::vec::Vec;
+///
+/// let fruits = vec!["apple", "banana", "tangerine", "watermelon"];
+/// let filter: FastProxy<_, Xor8> = FastProxy::from(&fruits);
+///
+/// assert!(filter.contains(&"tangerine"));
+/// ```
+///
+/// `AHasher::default()` uses fixed keys, but ahash's output is not stable across ahash versions
+/// or platforms (for instance, with and without AES instructions). A serialized `FastProxy` may
+/// not be queryable by another build; persist filters built with a stable hasher, such as
+/// [`Murmur3Hasher`], instead. Like any `HashProxy`, a `FastProxy` is unkeyed; use a
+/// [`KeyedHashProxy`] for keys from untrusted sources.
+///
+/// [`AHasher`]: https://docs.rs/ahash/latest/ahash/struct.AHasher.html
+/// [`Murmur3Hasher`]: crate::Murmur3Hasher
+/// [`KeyedHashProxy`]: crate::KeyedHashProxy
+#[cfg(feature = "ahash")]
+pub type FastProxy<T, F> = HashProxy<T, ahash::AHasher, F>;
+
 #[inline]
-fn hash<T: Hash, H: Hasher + Default>(key: &T) -> u64 {
+fn hash<T: Hash + ?Sized, H: Hasher + Default>(key: &T) -> u64 {
     let mut hasher = H::default();
     key.hash(&mut hasher);
     hasher.finish()
@@ -112,6 +165,7 @@ where
     F: Filter<u64>,
 {
     /// Returns `true` if the underlying filter contains the specified key.
+    #[inline]
     fn contains(&self, key: &T) -> bool {
         self.filter.contains(&hash::<T, H>(key))
     }
@@ -129,30 +183,139 @@ where
 {
     fn from(keys: &[T]) -> Self {
         let keys: Vec<u64> = keys.iter().map(hash::<T, H>).collect();
+        Self::from_inner(F::from(keys))
+    }
+}
+
+impl<T, H, F> From<&Vec<T>> for HashProxy<T, H, F>
+where
+    T: Hash,
+    H: Hasher + Default,
+    F: Filter<u64> + From<Vec<u64>>,
+{
+    fn from(v: &Vec<T>) -> Self {
+        Self::from(v.as_slice())
+    }
+}
+
+impl<T, H, F> HashProxy<T, H, F>
+where
+    T: Hash,
+    H: Hasher + Default,
+    F: Filter<u64>,
+{
+    /// Construct the proxy from an underlying filter, such as one previously obtained with
+    /// [`into_inner`]. The filter must have been constructed from keys hashed with `H`.
+    ///
+    /// [`into_inner`]: Self::into_inner
+    pub const fn from_inner(filter: F) -> Self {
         Self {
-            filter: F::from(keys),
+            filter,
             _hasher: core::marker::PhantomData,
             _type: core::marker::PhantomData,
         }
     }
+
+    /// Returns a reference to the underlying filter.
+    pub const fn inner(&self) -> &F {
+        &self.filter
+    }
+
+    /// Consumes the proxy, returning the underlying filter.
+    pub fn into_inner(self) -> F {
+        self.filter
+    }
+
+    /// Hashes `key` the way the proxy does, which may be any borrowed form of the proxy's key
+    /// type. A filter constructed from hashed keys can be wrapped with [`from_inner`], and hashed
+    /// keys can be looked up in the [`inner`] filter directly.
+    ///
+    /// ```
+    /// # extern crate alloc;
+    /// # extern crate std;
+    /// use std::collections::hash_map::DefaultHasher;
+    /// use xorf::{Filter, HashProxy, Xor8};
+    /// # use alloc::vec::Vec;
+    ///
+    /// type Fruits = HashProxy<&'static str, DefaultHasher, Xor8>;
+    ///
+    /// let hashes: Vec<u64> = ["apple", "banana", "tangerine"]
+    ///     .iter()
+    ///     .map(Fruits::hash_key)
+    ///     .collect();
+    /// let fruits = Fruits::from_inner(Xor8::from(&hashes));
+    ///
+    /// assert!(fruits.contains(&"tangerine"));
+    /// assert!(fruits.inner().contains(&Fruits::hash_key(&"tangerine")));
+    /// ```
+    ///
+    /// [`from_inner`]: Self::from_inner
+    /// [`inner`]: Self::inner
+    pub fn hash_key<Q>(key: &Q) -> u64
+    where
+        T: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        hash::<Q, H>(key)
+    }
+
+    /// Returns `true` if the underlying filter contains the specified key, which may be any
+    /// borrowed form of the proxy's key type. For example, a `HashProxy<String, _, _>` can be
+    /// queried with a `&str`.
+    ///
+    /// As with [`HashMap`], `Q`'s [`Hash`] implementation must match that of `T`.
+    ///
+    /// [`HashMap`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        self.filter.contains(&hash::<Q, H>(key))
+    }
 }
 
-impl<T, H, F> From<&Vec<T>> for HashProxy<T, H, F>
+impl<T, H, F> HashProxy<T, H, F>
 where
     T: Hash,
     H: Hasher + Default,
     F: Filter<u64> + From<Vec<u64>>,
 {
-    fn from(v: &Vec<T>) -> Self {
-        Self::from(v.as_slice())
+    /// Construct the proxy from an iterator of keys, such as one streaming keys from a file.
+    ///
+    /// Each key is hashed as it is consumed. Since the underlying filter iterates over its keys
+    /// multiple times during construction, the hashed keys are collected into a scratch
+    /// `Vec<u64>`.
+    pub fn from_iterator<I>(keys: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let keys: Vec<u64> = keys.into_iter().map(|key| hash::<T, H>(&key)).collect();
+        Self::from_inner(F::from(keys))
     }
 }
 
-// TODO(ayazhafiz): We should support a `TryFrom` trait as well. Today this is impossible due to
-// rustc's core blanket implementation of `Into`, which picks up a conflicting implementation when
-// both `From<T>` and `TryFrom<T>` with unbound type parameters `T` are defined.
+// NB: We would like to support a `TryFrom` trait as well. Today this is impossible due to rustc's
+// core blanket implementation of `Into`, which picks up a conflicting implementation when both
+// `From<T>` and `TryFrom<T>` with unbound type parameters `T` are defined. `try_from_keys` is
+// provided instead.
 //
 // See https://github.com/rust-lang/rust/issues/50133 for more details.
+impl<T, H, F> HashProxy<T, H, F>
+where
+    T: Hash,
+    H: Hasher + Default,
+    F: Filter<u64> + TryFrom<Vec<u64>>,
+{
+    /// Try to construct the proxy from a slice of keys, using an underlying filter whose
+    /// construction may fail, like a [`BinaryFuse8`].
+    ///
+    /// [`BinaryFuse8`]: crate::BinaryFuse8
+    pub fn try_from_keys(keys: &[T]) -> Result<Self, F::Error> {
+        let keys: Vec<u64> = keys.iter().map(hash::<T, H>).collect();
+        F::try_from(keys).map(Self::from_inner)
+    }
+}
 
 #[cfg(test)]
 mod test {
@@ -167,11 +330,8 @@ mod test {
     use std::collections::hash_map::DefaultHasher;
     use std::string::String;
 
-    #[test]
-    fn test_initialization_from() {
-        const SAMPLE_SIZE: usize = 1_000_000;
-        // Key generation is expensive. Do it once and make copies during tests.
-        let keys: Vec<String> = (0..SAMPLE_SIZE)
+    fn random_keys(size: usize) -> Vec<String> {
+        (0..size)
             .map(|_| {
                 rand::thread_rng()
                     .sample_iter(&Alphanumeric)
@@ -179,7 +339,14 @@ mod test {
                     .map(char::from)
                     .collect()
             })
-            .collect();
+            .collect()
+    }
+
+    #[test]
+    fn test_initialization_from() {
+        const SAMPLE_SIZE: usize = 1_000_000;
+        // Key generation is expensive. Do it once and make copies during tests.
+        let keys = random_keys(SAMPLE_SIZE);
 
         macro_rules! drive_test {
             ($xorf:ident) => {{
@@ -195,4 +362,150 @@ mod test {
         drive_test!(Xor16);
         drive_test!(Xor32);
     }
+
+    #[test]
+    fn test_initialization_from_iterator() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let keys = random_keys(SAMPLE_SIZE);
+
+        let filter: HashProxy<_, DefaultHasher, Xor8> = HashProxy::from_iterator(keys.clone());
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ahash")]
+    fn test_fast_proxy() {
+        use crate::FastProxy;
+
+        const SAMPLE_SIZE: usize = 100_000;
+        let keys = random_keys(SAMPLE_SIZE);
+
+        let filter: FastProxy<_, Xor8> = FastProxy::from(&keys);
+        for key in keys.iter() {
+            assert!(filter.contains(key));
+            assert!(filter.contains_key(key.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_contains_key() {
+        let keys: Vec<String> = ["apple", "banana", "tangerine", "watermelon"]
+            .iter()
+            .map(|s| String::from(*s))
+            .collect();
+        let filter: HashProxy<_, DefaultHasher, Xor8> = HashProxy::from(&keys);
+
+        for key in ["apple", "banana", "tangerine", "watermelon"] {
+            assert!(filter.contains_key(key));
+        }
+    }
+
+    #[test]
+    fn test_hash_key() {
+        type Proxy<F> = HashProxy<String, DefaultHasher, F>;
+
+        let keys: Vec<String> = (0..10_000).map(|i| format!("key-{}", i)).collect();
+        let hashes: Vec<u64> = keys.iter().map(Proxy::<Xor8>::hash_key).collect();
+        let small = Proxy::from_inner(Xor8::from(&hashes));
+        let large = Proxy::from_inner(Xor16::from(&hashes));
+
+        for key in &keys {
+            assert!(small.contains(key));
+            assert!(large.contains_key(key.as_str()));
+        }
+        for key in (0..10_000).map(|i| format!("other-{}", i)) {
+            let hash = Proxy::<Xor8>::hash_key(key.as_str());
+            assert_eq!(small.contains(&key), small.inner().contains(&hash));
+        }
+    }
+
+    #[test]
+    fn test_inner() {
+        let keys: Vec<String> = ["apple", "banana", "tangerine", "watermelon"]
+            .iter()
+            .map(|s| String::from(*s))
+            .collect();
+        let filter: HashProxy<_, DefaultHasher, Xor8> = HashProxy::from(&keys);
+        assert_eq!(filter.inner().len(), filter.len());
+
+        let inner: Xor8 = filter.into_inner();
+        let filter: HashProxy<String, DefaultHasher, Xor8> = HashProxy::from_inner(inner);
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "binary-fuse")]
+    fn test_initialization_try_from_keys() {
+        use crate::{BinaryFuse16, BinaryFuse32, BinaryFuse8};
+
+        const SAMPLE_SIZE: usize = 100_000;
+        let keys = random_keys(SAMPLE_SIZE);
+
+        macro_rules! drive_test {
+            ($xorf:ident) => {{
+                let filter: HashProxy<_, DefaultHasher, $xorf> =
+                    HashProxy::try_from_keys(&keys).unwrap();
+                for key in keys.iter() {
+                    assert!(filter.contains(key));
+                }
+            }};
+        }
+
+        drive_test!(BinaryFuse8);
+        drive_test!(BinaryFuse16);
+        drive_test!(BinaryFuse32);
+    }
+
+    #[test]
+    #[cfg(feature = "bincode")]
+    fn test_bincode_roundtrip() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let keys = random_keys(SAMPLE_SIZE);
+
+        let filter: HashProxy<_, DefaultHasher, Xor8> = HashProxy::from(&keys);
+        let config = bincode::config::standard();
+        let bytes = bincode::encode_to_vec(&filter, config).unwrap();
+        let (deserialized, read): (HashProxy<String, DefaultHasher, Xor8>, usize) =
+            bincode::decode_from_slice(&bytes, config).unwrap();
+
+        assert_eq!(read, bytes.len());
+        // The key count is only recorded by `to_bytes`, so compare the rest of the filter.
+        assert_eq!(filter.inner().seed, deserialized.inner().seed);
+        assert_eq!(
+            filter.inner().fingerprints,
+            deserialized.inner().fingerprints
+        );
+        for key in keys {
+            assert!(deserialized.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_send_sync() {
+        use core::hash::Hasher;
+        use core::marker::PhantomData;
+        use std::rc::Rc;
+
+        // A hasher that is neither `Send` nor `Sync`.
+        #[derive(Default)]
+        struct LocalHasher(DefaultHasher, PhantomData<*const ()>);
+
+        impl Hasher for LocalHasher {
+            fn finish(&self) -> u64 {
+                self.0.finish()
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                self.0.write(bytes)
+            }
+        }
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<HashProxy<String, DefaultHasher, Xor8>>();
+        assert_send_sync::<HashProxy<Rc<str>, LocalHasher, Xor8>>();
+    }
 }
@@ -0,0 +1,190 @@
+//! The hash functions filters use to look up keys.
+//!
+//! These are the functions [`Filter::contains`] is built from, exposed so that structures outside
+//! this crate, such as a custom on-disk layout of a filter's fingerprints, can locate and check
+//! keys the same way. They are stable: a filter serialized by one version of this crate hashes
+//! keys the same way in later versions.
+//!
+//! An `Xor` filter contains a key if the key's fingerprint equals the xor of the fingerprints at
+//! its three indices:
+//!
+//! ```
+//! # extern crate alloc;
+//! use xorf::{hashing, Filter, Xor8};
+//! # use alloc::vec::Vec;
+//!
+//! let keys: Vec<u64> = (0..10_000).collect();
+//! let filter = Xor8::from(&keys);
+//!
+//! let hash = hashing::mix(42, filter.seed);
+//! let (h0, h1, h2) = hashing::xor_indices(hash, filter.block_length);
+//! let fingerprints = &filter.fingerprints;
+//! let xor = fingerprints[h0] ^ fingerprints[h1] ^ fingerprints[h2];
+//! assert_eq!(hashing::fingerprint(hash) as u8 == xor, filter.contains(&42));
+//! ```
+//!
+//! `BinaryFuse` filters do the same with the indices returned by `bfuse_indices`, with the
+//! `binary-fuse` feature.
+//!
+//! [`Filter::contains`]: crate::Filter::contains
+
+/// Mixes `key` with the `seed` of a filter, producing the hash the filter derives the key's
+/// fingerprint and indices from.
+pub const fn mix(key: u64, seed: u64) -> u64 {
+    crate::prelude::mix(key, seed)
+}
+
+/// Folds a hash produced by [`mix`] into the value fingerprints are taken from.
+///
+/// An `N`-bit fingerprint is the low `N` bits of the result, except that 64-bit fingerprints are
+/// the hash itself. This is the same as [`Fingerprint::from_hash`].
+///
+/// [`Fingerprint::from_hash`]: crate::Fingerprint::from_hash
+pub const fn fingerprint(hash: u64) -> u64 {
+    crate::fingerprint!(hash)
+}
+
+/// Returns the indices of the three fingerprints an `Xor` filter xors together for a key with
+/// hash `hash`, as produced by [`mix`]. `block_length` is the filter's [`Xor::block_length`].
+///
+/// [`Xor::block_length`]: crate::Xor::block_length
+pub const fn xor_indices(hash: u64, block_length: usize) -> (usize, usize, usize) {
+    let [h0, h1, h2] = crate::prelude::xor::xor_indices(hash, block_length);
+    (h0, h1 + block_length, h2 + 2 * block_length)
+}
+
+/// Returns the indices of the three fingerprints a `BinaryFuse` filter xors together for a key
+/// with hash `hash`, as produced by [`mix`].
+///
+/// The filter parameters are those returned by [`BinaryFuse::segment_length`],
+/// [`BinaryFuse::segment_length_mask`], and [`BinaryFuse::segment_count_length`].
+///
+/// ```
+/// # extern crate alloc;
+/// use xorf::{hashing, BinaryFuse8, Filter};
+/// use core::convert::TryFrom;
+/// # use alloc::vec::Vec;
+///
+/// let keys: Vec<u64> = (0..10_000).collect();
+/// let filter = BinaryFuse8::try_from(&keys).unwrap();
+///
+/// let hash = hashing::mix(42, filter.seed());
+/// let (h0, h1, h2) = hashing::bfuse_indices(
+///     hash,
+///     filter.segment_length(),
+///     filter.segment_length_mask(),
+///     filter.segment_count_length(),
+/// );
+/// let fingerprints = &filter.fingerprints;
+/// let xor = fingerprints[h0 as usize] ^ fingerprints[h1 as usize] ^ fingerprints[h2 as usize];
+/// assert_eq!(hashing::fingerprint(hash) as u8 == xor, filter.contains(&42));
+/// ```
+///
+/// [`BinaryFuse::segment_length`]: crate::BinaryFuse::segment_length
+/// [`BinaryFuse::segment_length_mask`]: crate::BinaryFuse::segment_length_mask
+/// [`BinaryFuse::segment_count_length`]: crate::BinaryFuse::segment_count_length
+#[cfg(feature = "binary-fuse")]
+pub const fn bfuse_indices(
+    hash: u64,
+    segment_length: u32,
+    segment_length_mask: u32,
+    segment_count_length: u32,
+) -> (u32, u32, u32) {
+    crate::prelude::bfuse::hash_of_hash(
+        hash,
+        segment_length,
+        segment_length_mask,
+        segment_count_length,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{hashing, Fingerprint, U24};
+
+    use rand::Rng;
+
+    #[test]
+    fn test_fingerprint() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1_000 {
+            let hash = hashing::mix(rng.gen(), rng.gen());
+            let fingerprint = hashing::fingerprint(hash);
+            assert_eq!(u8::from_hash(hash), fingerprint as u8);
+            assert_eq!(u16::from_hash(hash), fingerprint as u16);
+            assert_eq!(U24::from_hash(hash).get(), fingerprint as u32 & 0xff_ffff);
+            assert_eq!(u32::from_hash(hash), fingerprint as u32);
+        }
+    }
+
+    #[test]
+    fn test_xor_indices() {
+        use crate::{Filter, Xor16};
+        use alloc::vec::Vec;
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..1_000 {
+            // Keys must keep mapping to the slots `xor_h!` assigns them.
+            let (hash, block_length): (u64, usize) = (rng.gen(), rng.gen_range(1..1 << 30));
+            assert_eq!(
+                hashing::xor_indices(hash, block_length),
+                (
+                    crate::xor_h!(index block 0, of length block_length, using hash),
+                    crate::xor_h!(index block 1, of length block_length, using hash) + block_length,
+                    crate::xor_h!(index block 2, of length block_length, using hash)
+                        + 2 * block_length,
+                )
+            );
+        }
+
+        const SAMPLE_SIZE: usize = 100_000;
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        let filter = Xor16::from(&keys);
+
+        let contains = |key: u64| {
+            let hash = hashing::mix(key, filter.seed);
+            let (h0, h1, h2) = hashing::xor_indices(hash, filter.block_length);
+            let fingerprints = &filter.fingerprints;
+            hashing::fingerprint(hash) as u16
+                == fingerprints[h0] ^ fingerprints[h1] ^ fingerprints[h2]
+        };
+        for key in keys {
+            assert!(contains(key));
+        }
+        for key in (0..SAMPLE_SIZE).map(|_| rng.gen()) {
+            assert_eq!(contains(key), filter.contains(&key));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "binary-fuse")]
+    fn test_bfuse_indices() {
+        use crate::{BinaryFuse16, Filter};
+        use alloc::vec::Vec;
+        use core::convert::TryFrom;
+
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        let filter = BinaryFuse16::try_from(&keys).unwrap();
+
+        let contains = |key: u64| {
+            let hash = hashing::mix(key, filter.seed());
+            let (h0, h1, h2) = hashing::bfuse_indices(
+                hash,
+                filter.segment_length(),
+                filter.segment_length_mask(),
+                filter.segment_count_length(),
+            );
+            let fingerprints = &filter.fingerprints;
+            hashing::fingerprint(hash) as u16
+                == fingerprints[h0 as usize] ^ fingerprints[h1 as usize] ^ fingerprints[h2 as usize]
+        };
+        for key in keys {
+            assert!(contains(key));
+        }
+        for key in (0..SAMPLE_SIZE).map(|_| rng.gen()) {
+            assert_eq!(contains(key), filter.contains(&key));
+        }
+    }
+}
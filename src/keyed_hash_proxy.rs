@@ -0,0 +1,241 @@
+//! Implements a hashing proxy for xor filters using a keyed hasher.
+
+use crate::Filter;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::convert::TryFrom;
+use core::hash::{BuildHasher, Hash};
+
+/// Arbitrary key type proxy for xor filters, hashing keys with hashers built by a [`BuildHasher`].
+///
+/// A [`HashProxy`] hashes keys with a `Default` hasher, so anyone who knows the hasher can
+/// compute the hashes of keys and search for keys that collide with ones in the filter. A
+/// `KeyedHashProxy` instead stores a [`BuildHasher`], like a [`RandomState`] or a SipHash builder
+/// with a secret key, and hashes both the keys it is constructed from and the keys it is queried
+/// with using hashers built from it. Filters constructed with different hasher keys have
+/// different false positives.
+///
+/// ```
+/// # extern crate alloc;
+/// # extern crate std;
+/// use std::collections::hash_map::RandomState;
+/// use xorf::{Filter, KeyedHashProxy, Xor8};
+///
+/// let blocklist = ["mallory", "trudy", "eve"];
+/// let filter: KeyedHashProxy<_, _, Xor8> =
+///     KeyedHashProxy::from_keys(&blocklist, RandomState::new());
+///
+/// for name in blocklist.iter() {
+///     assert!(filter.contains(name));
+/// }
+/// ```
+///
+/// The hasher builder is part of the filter: the underlying filter can only be queried with
+/// hashes from a builder with the same key. Unlike a `HashProxy`, a `KeyedHashProxy` can't be
+/// serialized, so that the hasher key isn't persisted alongside the filter by accident;
+/// serialize the [`inner`] filter and keep the hasher key separately instead.
+///
+/// [`HashProxy`]: crate::HashProxy
+/// [`RandomState`]: https://doc.rust-lang.org/std/collections/hash_map/struct.RandomState.html
+/// [`inner`]: Self::inner
+pub struct KeyedHashProxy<T, S, F>
+where
+    T: Hash,
+    S: BuildHasher,
+    F: Filter<u64>,
+{
+    filter: F,
+    build_hasher: S,
+    // A `KeyedHashProxy` holds no keys, so whether it is `Send` or `Sync` must not depend on `T`.
+    _type: core::marker::PhantomData<fn() -> T>,
+}
+
+#[inline]
+fn hash<T: Hash + ?Sized, S: BuildHasher>(build_hasher: &S, key: &T) -> u64 {
+    build_hasher.hash_one(key)
+}
+
+impl<T, S, F> Filter<T> for KeyedHashProxy<T, S, F>
+where
+    T: Hash,
+    S: BuildHasher,
+    F: Filter<u64>,
+{
+    /// Returns `true` if the underlying filter contains the specified key.
+    #[inline]
+    fn contains(&self, key: &T) -> bool {
+        self.filter.contains(&hash(&self.build_hasher, key))
+    }
+
+    fn len(&self) -> usize {
+        self.filter.len()
+    }
+}
+
+impl<T, S, F> KeyedHashProxy<T, S, F>
+where
+    T: Hash,
+    S: BuildHasher,
+    F: Filter<u64>,
+{
+    /// Construct the proxy from an underlying filter, such as one previously obtained with
+    /// [`into_parts`]. The filter must have been constructed from keys hashed with hashers built
+    /// by `build_hasher`.
+    ///
+    /// [`into_parts`]: Self::into_parts
+    pub const fn from_parts(filter: F, build_hasher: S) -> Self {
+        Self {
+            filter,
+            build_hasher,
+            _type: core::marker::PhantomData,
+        }
+    }
+
+    /// Returns a reference to the underlying filter.
+    pub const fn inner(&self) -> &F {
+        &self.filter
+    }
+
+    /// Returns a reference to the builder of the hashers keys are hashed with.
+    pub const fn hasher(&self) -> &S {
+        &self.build_hasher
+    }
+
+    /// Consumes the proxy, returning the underlying filter and the hasher builder.
+    pub fn into_parts(self) -> (F, S) {
+        (self.filter, self.build_hasher)
+    }
+
+    /// Hashes `key` the way the proxy does, which may be any borrowed form of the proxy's key
+    /// type. Hashed keys can be looked up in the [`inner`] filter directly.
+    ///
+    /// [`inner`]: Self::inner
+    pub fn hash_key<Q>(&self, key: &Q) -> u64
+    where
+        T: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        hash(&self.build_hasher, key)
+    }
+
+    /// Returns `true` if the underlying filter contains the specified key, which may be any
+    /// borrowed form of the proxy's key type.
+    ///
+    /// As with [`HashMap`], `Q`'s [`Hash`] implementation must match that of `T`.
+    ///
+    /// [`HashMap`]: https://doc.rust-lang.org/std/collections/struct.HashMap.html
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + ?Sized,
+    {
+        self.filter.contains(&self.hash_key(key))
+    }
+}
+
+impl<T, S, F> KeyedHashProxy<T, S, F>
+where
+    T: Hash,
+    S: BuildHasher,
+    F: Filter<u64> + From<Vec<u64>>,
+{
+    /// Construct the proxy from a slice of keys, hashing them with hashers built by
+    /// `build_hasher`.
+    pub fn from_keys(keys: &[T], build_hasher: S) -> Self {
+        let keys: Vec<u64> = keys.iter().map(|key| hash(&build_hasher, key)).collect();
+        Self::from_parts(F::from(keys), build_hasher)
+    }
+}
+
+impl<T, S, F> KeyedHashProxy<T, S, F>
+where
+    T: Hash,
+    S: BuildHasher,
+    F: Filter<u64> + TryFrom<Vec<u64>>,
+{
+    /// Try to construct the proxy from a slice of keys, hashing them with hashers built by
+    /// `build_hasher`, using an underlying filter whose construction may fail, like a
+    /// [`BinaryFuse8`].
+    ///
+    /// [`BinaryFuse8`]: crate::BinaryFuse8
+    pub fn try_from_keys(keys: &[T], build_hasher: S) -> Result<Self, F::Error> {
+        let keys: Vec<u64> = keys.iter().map(|key| hash(&build_hasher, key)).collect();
+        F::try_from(keys).map(|filter| Self::from_parts(filter, build_hasher))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Filter, KeyedHashProxy, Xor8};
+
+    use alloc::vec::Vec;
+    use core::hash::{BuildHasher, Hasher};
+    use rand::distributions::Alphanumeric;
+    use rand::Rng;
+
+    extern crate std;
+    use std::collections::hash_map::DefaultHasher;
+    use std::string::String;
+
+    // Keys a `DefaultHasher` by hashing a secret key ahead of each value.
+    struct Keyed(u64);
+
+    impl BuildHasher for Keyed {
+        type Hasher = DefaultHasher;
+
+        fn build_hasher(&self) -> DefaultHasher {
+            let mut hasher = DefaultHasher::default();
+            hasher.write_u64(self.0);
+            hasher
+        }
+    }
+
+    fn random_keys(size: usize) -> Vec<String> {
+        (0..size)
+            .map(|_| {
+                rand::thread_rng()
+                    .sample_iter(&Alphanumeric)
+                    .take(15)
+                    .map(char::from)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_initialization_with_keys() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let keys = random_keys(SAMPLE_SIZE);
+
+        let a: KeyedHashProxy<_, _, Xor8> = KeyedHashProxy::from_keys(&keys, Keyed(1));
+        let b: KeyedHashProxy<_, _, Xor8> = KeyedHashProxy::from_keys(&keys, Keyed(2));
+        for key in keys.iter() {
+            assert!(a.contains(key));
+            assert!(b.contains(key));
+            assert!(a.contains_key(key.as_str()));
+            assert_ne!(a.hash_key(key), b.hash_key(key));
+        }
+        assert_ne!(a.inner(), b.inner());
+    }
+
+    #[test]
+    #[cfg(feature = "binary-fuse")]
+    fn test_try_from_keys() {
+        use crate::BinaryFuse16;
+
+        const SAMPLE_SIZE: usize = 100_000;
+        let keys = random_keys(SAMPLE_SIZE);
+
+        let filter: KeyedHashProxy<_, _, BinaryFuse16> =
+            KeyedHashProxy::try_from_keys(&keys, Keyed(42)).unwrap();
+        for key in keys.iter() {
+            assert!(filter.contains(key));
+        }
+
+        let (inner, build_hasher) = filter.into_parts();
+        let filter: KeyedHashProxy<String, _, _> = KeyedHashProxy::from_parts(inner, build_hasher);
+        for key in keys.iter() {
+            assert!(filter.inner().contains(&filter.hash_key(key)));
+        }
+    }
+}
@@ -32,16 +32,35 @@
 //! rate than both a `BinaryFuseN` and `FuseN` filter.
 //!
 //! A `BinaryFuseN` filter's construction is faster, uses less memory, and is more likely to
-//! succeed compared to a `FuseN` filter's construction.
+//! succeed compared to a `FuseN` filter's construction. `BinaryFuse` filters index their
+//! fingerprints with 32-bit integers, and fail to construct from more than about 3.8 billion keys.
 //!
 //! The false-positive rate of a filter with fingerprint size `N` is around `2^{-N}`; for more
 //! numbers, see the documentation of each individual filter.
 //!
+//! ### Unused fingerprint slots
+//!
+//! Filters hold more fingerprint slots than keys, and no key is assigned to some of them. `Xor`
+//! filters leave unused slots zero, as do `Fuse` and `BinaryFuse` filters unless they fill them
+//! with random values (see [reproducible construction](#reproducible-construction)). Zeroed
+//! slots make keys with a zero fingerprint more likely to be false positives. Since fingerprints
+//! are uniformly distributed, the false positive rate averaged over all keys, which is what
+//! filters' `false_positive_rate` methods return, is unaffected.
+//!
 //! ### Assumed pre-conditions
 //!
 //! - It is a pre-condition that all filters are constructed from a data structure containing no
 //!   duplicate keys. You must perform any de-duplication needed yourself before constructing a
-//!   filter.
+//!   filter, for example with [`dedup`] or [`dedup_sorted`].
+//!
+//! ### Reproducible construction
+//!
+//! The default `uniform-random` feature fills unused fingerprint slots of `Fuse` and `BinaryFuse`
+//! filters with pseudo-random values seeded with the filter's seed, rather than zeroes.
+//! Constructing a filter from the same keys yields the same filter on every platform, with or
+//! without the feature, and construction needs no entropy source, so it also works on targets
+//! like `wasm32-unknown-unknown`. `BinaryFuse` filters constructed with
+//! `try_from_iterator_with_rng` fill unused slots from the given generator instead.
 //!
 //! ## FAQ
 //!
@@ -66,7 +85,6 @@
 #![warn(missing_docs)]
 #![forbid(clippy::all, clippy::cargo, clippy::nursery)]
 #![allow(
-    clippy::len_without_is_empty,
     clippy::useless_attribute,
     clippy::multiple_crate_versions,
     clippy::fallible_impl_from
@@ -74,40 +92,82 @@
 
 #[macro_use]
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 mod murmur3;
 mod prelude;
+mod probe;
 mod splitmix64;
 
+#[cfg(feature = "binary-fuse")]
+mod bfuse;
 #[cfg(feature = "binary-fuse")]
 mod bfuse16;
 #[cfg(feature = "binary-fuse")]
+mod bfuse24;
+#[cfg(feature = "binary-fuse")]
 mod bfuse32;
 #[cfg(feature = "binary-fuse")]
+mod bfuse64;
+#[cfg(feature = "binary-fuse")]
 mod bfuse8;
+mod boxed;
+mod dedup;
+mod error;
+mod fill_rng;
+mod fingerprint;
 mod fuse16;
 mod fuse32;
 mod fuse8;
 mod hash_proxy;
+pub mod hashing;
+mod keyed_hash_proxy;
+mod matching;
+mod sharded;
+mod shared_xor;
+mod xor;
 mod xor16;
 mod xor32;
+mod xor64;
 mod xor8;
 
+#[cfg(feature = "binary-fuse")]
+pub use bfuse::{BinaryFuse, BuildStats, Descriptor};
 #[cfg(feature = "binary-fuse")]
 pub use bfuse16::BinaryFuse16;
 #[cfg(feature = "binary-fuse")]
+pub use bfuse24::BinaryFuse24;
+#[cfg(feature = "binary-fuse")]
 pub use bfuse32::BinaryFuse32;
 #[cfg(feature = "binary-fuse")]
-pub use bfuse8::BinaryFuse8;
+pub use bfuse64::BinaryFuse64;
+#[cfg(feature = "binary-fuse")]
+pub use bfuse8::{bfuse8_contains, BinaryFuse8, BinaryFuse8Builder};
+pub use boxed::{build_boxed, FilterKind};
+pub use dedup::{dedup, dedup_sorted};
+pub use error::BuildError;
+pub use fill_rng::{FillRng, SplitMix64};
+pub use fingerprint::{Fingerprint, U24};
 #[allow(deprecated)]
 pub use fuse16::Fuse16;
 #[allow(deprecated)]
 pub use fuse32::Fuse32;
 #[allow(deprecated)]
 pub use fuse8::Fuse8;
+#[cfg(feature = "ahash")]
+pub use hash_proxy::FastProxy;
 pub use hash_proxy::HashProxy;
+pub use keyed_hash_proxy::KeyedHashProxy;
+pub use matching::matching_indices;
+pub use murmur3::Murmur3Hasher;
+pub use probe::Probe;
+pub use sharded::ShardedFilter;
+pub use shared_xor::SharedXor;
+pub use xor::Xor;
 pub use xor16::Xor16;
 pub use xor32::Xor32;
+pub use xor64::Xor64;
 pub use xor8::Xor8;
 
 /// Methods common to xor filters.
@@ -119,5 +179,177 @@ pub trait Filter<Type> {
     fn contains(&self, key: &Type) -> bool;
 
     /// Returns the number of fingerprints in the filter.
+    ///
+    /// This is the number of fingerprint slots, which is larger than the number of keys the filter
+    /// was constructed from; see [`slot_count`].
+    ///
+    /// [`slot_count`]: Self::slot_count
     fn len(&self) -> usize;
+
+    /// Returns the number of fingerprint slots in the filter, the same as [`len`].
+    ///
+    /// Filters hold more slots than keys, so the bits per entry of a filter must be computed over
+    /// the number of keys it was constructed from, not over its slot count:
+    ///
+    /// ```
+    /// # extern crate alloc;
+    /// use xorf::{Filter, Xor8};
+    /// # use alloc::vec::Vec;
+    ///
+    /// let keys: Vec<u64> = (0..10_000).collect();
+    /// let filter = Xor8::from(&keys);
+    /// assert_eq!(filter.key_count(), keys.len());
+    /// assert!(filter.slot_count() > filter.key_count());
+    ///
+    /// let bits_per_entry = (filter.slot_count() * 8) as f64 / filter.key_count() as f64;
+    /// assert!(bits_per_entry < 10.0);
+    /// ```
+    ///
+    /// `Xor` and `BinaryFuse` filters record the number of keys they were constructed from, which
+    /// their `key_count` methods return.
+    ///
+    /// [`len`]: Self::len
+    fn slot_count(&self) -> usize {
+        self.len()
+    }
+
+    /// Returns `true` if the filter has no fingerprints.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the filter probably contains `key` converted to a `Type`, such as a
+    /// `u32` key of a filter of `u64`s. This saves spelling out the conversion at each call.
+    ///
+    /// A key is looked up by its converted value, so a filter constructed from converted keys
+    /// contains the original ones:
+    ///
+    /// ```
+    /// # extern crate alloc;
+    /// use xorf::{Filter, Xor8};
+    /// # use alloc::vec::Vec;
+    ///
+    /// let ids: Vec<u32> = (0..1_000).collect();
+    /// let filter = Xor8::from_iterator(ids.iter().map(|&id| u64::from(id)));
+    /// assert!(ids.iter().all(|&id| filter.contains_converted(id)));
+    /// ```
+    ///
+    /// `Filter` is only implemented for the key type a filter is constructed from, rather than
+    /// for every convertible type, so that the type of an integer literal passed to [`contains`]
+    /// can still be inferred.
+    ///
+    /// [`contains`]: Self::contains
+    fn contains_converted<K: Into<Type>>(&self, key: K) -> bool
+    where
+        Self: Sized,
+    {
+        self.contains(&key.into())
+    }
+
+    /// Checks whether the filter probably contains each of `keys`, writing the result for
+    /// `keys[i]` to `out[i]`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `keys` and `out` have different lengths.
+    fn contains_many(&self, keys: &[Type], out: &mut [bool]) {
+        assert_eq!(
+            keys.len(),
+            out.len(),
+            "keys and out must have the same length"
+        );
+        for (key, out) in keys.iter().zip(out.iter_mut()) {
+            *out = self.contains(key);
+        }
+    }
+
+    /// Returns the number of `keys` the filter probably contains.
+    fn contains_count(&self, keys: &[Type]) -> usize {
+        keys.iter().filter(|key| self.contains(key)).count()
+    }
+
+    /// Returns the fraction of `keys` the filter probably contains, or `0.0` if `keys` is empty.
+    ///
+    /// This estimates how much of a candidate key set overlaps the keys the filter was constructed
+    /// from. Keys the filter wasn't constructed from are still counted with the filter's false
+    /// positive probability, so the coverage of a disjoint key set is about the false positive
+    /// rate rather than zero.
+    ///
+    /// ```
+    /// # extern crate alloc;
+    /// use xorf::{Filter, Xor16};
+    /// # use alloc::vec::Vec;
+    ///
+    /// let keys: Vec<u64> = (0..10_000).collect();
+    /// let filter = Xor16::from(&keys);
+    ///
+    /// let candidates: Vec<u64> = (5_000..15_000).collect();
+    /// assert!((filter.coverage(&candidates) - 0.5).abs() < 0.01);
+    /// ```
+    fn coverage(&self, keys: &[Type]) -> f64 {
+        if keys.is_empty() {
+            return 0.0;
+        }
+        self.contains_count(keys) as f64 / keys.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    #[allow(deprecated)]
+    fn test_filters_send_sync() {
+        use crate::*;
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Xor8>();
+        assert_send_sync::<Xor16>();
+        assert_send_sync::<Xor32>();
+        assert_send_sync::<Xor64>();
+        assert_send_sync::<Fuse8>();
+        assert_send_sync::<Fuse16>();
+        assert_send_sync::<Fuse32>();
+        assert_send_sync::<ShardedFilter<Xor8>>();
+        assert_send_sync::<SharedXor<u32>>();
+        #[cfg(feature = "binary-fuse")]
+        {
+            assert_send_sync::<BinaryFuse8>();
+            assert_send_sync::<BinaryFuse16>();
+            assert_send_sync::<BinaryFuse24>();
+            assert_send_sync::<BinaryFuse32>();
+            assert_send_sync::<BinaryFuse64>();
+            assert_send_sync::<BinaryFuse8Builder>();
+        }
+    }
+
+    #[test]
+    fn test_coverage() {
+        use crate::{Filter, Xor16};
+        use alloc::vec::Vec;
+
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter = Xor16::from(&keys);
+
+        assert_eq!(filter.contains_count(&keys), keys.len());
+        assert_eq!(filter.coverage(&keys), 1.0);
+        assert_eq!(filter.coverage(&keys[..100]), 1.0);
+        assert_eq!(filter.coverage(&[]), 0.0);
+
+        let candidates: Vec<u64> = (7_500..17_500).collect();
+        let count = filter.contains_count(&candidates);
+        assert!((2_500..2_510).contains(&count), "{} matching keys", count);
+        assert_eq!(filter.coverage(&candidates), count as f64 / 10_000.0);
+    }
+
+    #[test]
+    fn test_slot_count() {
+        use crate::{Filter, Xor8};
+        use alloc::vec::Vec;
+
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter = Xor8::from(&keys);
+        assert_eq!(filter.slot_count(), filter.len());
+        assert_eq!(filter.slot_count(), filter.fingerprints.len());
+        assert!(filter.slot_count() > keys.len());
+    }
 }
@@ -0,0 +1,61 @@
+//! Implements a helper for querying several filters at once.
+
+use crate::Filter;
+use alloc::vec::Vec;
+
+/// Returns the indices of the filters in `filters` that contain `key`, in ascending order.
+///
+/// Filters of different types can be queried together as `&dyn Filter<u64>`s, such as one filter
+/// per category of keys.
+///
+/// ```
+/// # extern crate alloc;
+/// use xorf::{Filter, Xor16, Xor8};
+///
+/// let spam = Xor8::from(&[1, 2, 3]);
+/// let malware = Xor16::from(&[3, 4, 5]);
+/// let phishing = Xor8::from(&[6, 7, 8]);
+///
+/// let filters: [&dyn Filter<u64>; 3] = [&spam, &malware, &phishing];
+/// assert_eq!(xorf::matching_indices(&filters, 3), [0, 1]);
+/// ```
+pub fn matching_indices(filters: &[&dyn Filter<u64>], key: u64) -> Vec<usize> {
+    filters
+        .iter()
+        .enumerate()
+        .filter(|(_, filter)| filter.contains(&key))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{matching_indices, Filter, Xor16, Xor32, Xor8};
+
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_matching_indices() {
+        let a: Vec<u64> = (0..1_000).collect();
+        let b: Vec<u64> = (500..1_500).collect();
+        let c: Vec<u64> = (1_000..2_000).collect();
+        let (a, b, c) = (Xor8::from(&a), Xor16::from(&b), Xor32::from(&c));
+        let filters: [&dyn Filter<u64>; 3] = [&a, &b, &c];
+
+        assert_eq!(matching_indices(&filters, 0), [0]);
+        assert_eq!(matching_indices(&filters, 700), [0, 1]);
+        assert_eq!(matching_indices(&filters, 1_200), [1, 2]);
+        assert_eq!(matching_indices(&filters, 1_999), [2]);
+        for key in 0..2_000 {
+            let matching = matching_indices(&filters, key);
+            assert!(matching.windows(2).all(|w| w[0] < w[1]));
+            assert_eq!(matching.contains(&0), key < 1_000 || a.contains(&key));
+            assert_eq!(matching.contains(&2), key >= 1_000 || c.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_no_filters() {
+        assert!(matching_indices(&[], 0).is_empty());
+    }
+}
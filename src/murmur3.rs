@@ -35,3 +35,240 @@ pub const fn mix64(mut k: u64) -> u64 {
     k ^= k >> 33;
     k
 }
+
+/// A [`Hasher`] built on [`MurmurHash3`]'s 64-bit finalization mix, which needs neither `std` nor
+/// any dependency.
+///
+/// It makes [`HashProxy`] usable in `no_std` builds, where `std`'s `DefaultHasher` is not
+/// available. It is fast but not keyed with a secret, so it offers no protection against keys
+/// chosen to collide; see [`KeyedHashProxy`] for that.
+///
+/// ```
+/// use xorf::{Filter, HashProxy, Murmur3Hasher, Xor8};
+///
+/// let fruits = ["apple", "banana", "tangerine", "watermelon"];
+/// let filter: HashProxy<_, Murmur3Hasher, Xor8> = HashProxy::from(&fruits[..]);
+///
+/// assert!(filter.contains(&"tangerine"));
+/// ```
+///
+/// The hash of a given sequence of writes is the same on every platform and in every version of
+/// the crate.
+///
+/// [`Hasher`]: core::hash::Hasher
+/// [`MurmurHash3`]: https://github.com/spaolacci/murmur3
+/// [`HashProxy`]: crate::HashProxy
+/// [`KeyedHashProxy`]: crate::KeyedHashProxy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Murmur3Hasher {
+    hash: u64,
+}
+
+impl Murmur3Hasher {
+    /// Creates a hasher whose hashes depend on `seed`. The default hasher has seed `0`.
+    pub const fn with_seed(seed: u64) -> Self {
+        Self { hash: seed }
+    }
+
+    /// Mixes one 64-bit word into the hash. Adding a constant keeps runs of zero words from
+    /// leaving the hash unchanged, since `mix64(0) == 0`.
+    #[inline]
+    const fn mix_word(&mut self, word: u64) {
+        self.hash = mix64((self.hash ^ word).wrapping_add(0x9e37_79b9_7f4a_7c15));
+    }
+}
+
+impl core::hash::Hasher for Murmur3Hasher {
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            self.mix_word(u64::from_le_bytes(word));
+        }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            // At most seven bytes remain, so the top byte is free to record how many, keeping
+            // trailing zero bytes significant.
+            let mut word = [0; 8];
+            word[..rest.len()].copy_from_slice(rest);
+            word[7] = rest.len() as u8;
+            self.mix_word(u64::from_le_bytes(word));
+        }
+    }
+
+    // The default integer methods write native-endian bytes, so they are overridden to write
+    // little-endian bytes, and `usize`s as `u64`s, for the same hashes on every platform.
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.mix_word(i);
+    }
+
+    #[inline]
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_i8(&mut self, i: i8) {
+        self.write_u8(i as u8);
+    }
+
+    #[inline]
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    #[inline]
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    #[inline]
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    #[inline]
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    #[inline]
+    fn write_isize(&mut self, i: isize) {
+        self.write_usize(i as usize);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Murmur3Hasher;
+    use core::hash::{Hash, Hasher};
+
+    fn hash<T: Hash + ?Sized>(value: &T) -> u64 {
+        let mut hasher = Murmur3Hasher::default();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_hashes_are_stable() {
+        let mut hasher = Murmur3Hasher::default();
+        hasher.write_u64(42);
+        assert_eq!(hasher.finish(), hash(&42u64));
+
+        let mut bytes = Murmur3Hasher::default();
+        bytes.write(&42u64.to_le_bytes());
+        assert_eq!(bytes.finish(), hasher.finish());
+
+        // Integers hash as their little-endian bytes, and `usize`s as `u64`s, on every platform.
+        let mut bytes = Murmur3Hasher::default();
+        bytes.write(&0x0102_0304u32.to_le_bytes());
+        assert_eq!(hash(&0x0102_0304u32), bytes.finish());
+        assert_eq!(hash(&-2i32), hash(&0xffff_fffeu32));
+        assert_eq!(hash(&42usize), hash(&42u64));
+        assert_eq!(hash(&-42isize), hash(&-42i64));
+    }
+
+    #[test]
+    fn test_distinct_inputs() {
+        let mut zeros = Murmur3Hasher::default();
+        zeros.write_u64(0);
+        let mut more_zeros = zeros;
+        more_zeros.write_u64(0);
+        assert_ne!(zeros.finish(), Murmur3Hasher::default().finish());
+        assert_ne!(zeros.finish(), more_zeros.finish());
+
+        let mut short = Murmur3Hasher::default();
+        short.write(&[1]);
+        let mut padded = Murmur3Hasher::default();
+        padded.write(&[1, 0]);
+        assert_ne!(short.finish(), padded.finish());
+
+        assert_ne!(hash("apple"), hash("banana"));
+        assert_ne!(
+            Murmur3Hasher::with_seed(1).finish(),
+            Murmur3Hasher::with_seed(2).finish()
+        );
+    }
+
+    #[test]
+    fn test_no_collisions() {
+        use alloc::collections::BTreeSet;
+        use alloc::format;
+
+        // Random 64-bit hashes of a million keys collide with probability around 2^-25.
+        const SAMPLE_SIZE: usize = 1_000_000;
+        let hashes: BTreeSet<u64> = (0..SAMPLE_SIZE)
+            .map(|i| hash(format!("key-{}", i).as_str()))
+            .collect();
+        assert_eq!(hashes.len(), SAMPLE_SIZE);
+
+        // Nearby strings hash to hashes with about half their bits different.
+        let flipped = (hash("key-0") ^ hash("key-1")).count_ones();
+        assert!((16..=48).contains(&flipped), "{} bits flipped", flipped);
+    }
+
+    #[test]
+    #[cfg(feature = "binary-fuse")]
+    fn test_binary_fuse_proxy() {
+        use crate::{BinaryFuse8, Filter, HashProxy};
+        use alloc::format;
+        use alloc::string::String;
+        use alloc::vec::Vec;
+
+        let keys: Vec<String> = (0..10_000).map(|i| format!("key-{}", i)).collect();
+        let filter: HashProxy<_, Murmur3Hasher, BinaryFuse8> =
+            HashProxy::try_from_keys(&keys).unwrap();
+        for key in keys.iter() {
+            assert!(filter.contains(key));
+        }
+    }
+
+    #[test]
+    fn test_hash_proxy() {
+        use crate::{Filter, HashProxy, Xor8};
+        use alloc::format;
+        use alloc::string::String;
+        use alloc::vec::Vec;
+
+        const SAMPLE_SIZE: usize = 100_000;
+        let keys: Vec<String> = (0..SAMPLE_SIZE).map(|i| format!("key-{}", i)).collect();
+        let filter: HashProxy<_, Murmur3Hasher, Xor8> = HashProxy::from(&keys);
+        for key in keys.iter() {
+            assert!(filter.contains(key));
+        }
+
+        let false_positives = (SAMPLE_SIZE..2 * SAMPLE_SIZE)
+            .filter(|i| filter.contains(&format!("key-{}", i)))
+            .count();
+        let fp_rate = (false_positives * 100) as f64 / SAMPLE_SIZE as f64;
+        assert!(fp_rate < 0.5, "False positive rate is {}", fp_rate);
+    }
+}
@@ -1,7 +1,8 @@
 //! Implements Binary Fuse filters.
 // Port of https://github.com/FastFilter/xorfilter/blob/master/binaryfusefilter.go
 
-use libm::{floor, fmax, log};
+use alloc::vec::Vec;
+use libm::{floor, fmax, log, round};
 
 #[inline]
 pub fn segment_length(arity: u32, size: u32) -> u32 {
@@ -44,6 +45,134 @@ pub const fn hash_of_hash(
     (h0, h1, h2)
 }
 
+/// Number of keys hashed together by [`hash_of_hash_x4`].
+#[cfg(feature = "simd")]
+pub const LANES: usize = 4;
+
+/// Computes the fingerprint hash and the three indices of [`hash_of_hash`] for `LANES` keys at a
+/// time, mixing the keys with `seed` as [`mix`] does.
+///
+/// The high half of `hash * segment_count_length` is computed from two 32-bit partial products,
+/// since there is no lane-wise 128-bit multiply. This is exact because `segment_count_length`
+/// fits in 32 bits.
+///
+/// [`mix`]: crate::prelude::mix
+#[cfg(feature = "simd")]
+#[inline]
+pub fn hash_of_hash_x4(
+    keys: &[u64],
+    seed: u64,
+    segment_length: u32,
+    segment_length_mask: u32,
+    segment_count_length: u32,
+) -> ([u64; LANES], [u64; LANES], [u64; LANES], [u64; LANES]) {
+    use wide::u64x4;
+
+    let mut hash = u64x4::new([keys[0], keys[1], keys[2], keys[3]]) + seed;
+    hash ^= hash >> 33_u32;
+    hash = hash * 0xff51_afd7_ed55_8ccd;
+    hash ^= hash >> 33_u32;
+    hash = hash * 0xc4ce_b9fe_1a85_ec53;
+    hash ^= hash >> 33_u32;
+
+    let segment_count_length = u64x4::splat(segment_count_length as u64);
+    let mask = u64x4::splat(segment_length_mask as u64);
+    let lo = (hash & u64x4::splat(0xffff_ffff)) * segment_count_length;
+    let hi = (hash >> 32_u32) * segment_count_length;
+    let h0 = (hi + (lo >> 32_u32)) >> 32_u32;
+    let h1 = h0 + segment_length as u64;
+    let h2 = h1 + segment_length as u64;
+    let h1 = h1 ^ ((hash >> 18_u32) & mask);
+    let h2 = h2 ^ (hash & mask);
+
+    let fingerprint = hash ^ (hash >> 32_u32);
+    (
+        fingerprint.to_array(),
+        h0.to_array(),
+        h1.to_array(),
+        h2.to_array(),
+    )
+}
+
+/// Checks that deserialized filter parameters are consistent with each other and with the number
+/// of fingerprints, so that lookups stay in bounds. An empty filter has all-zero parameters.
+pub const fn valid_params(
+    segment_length: u32,
+    segment_length_mask: u32,
+    segment_count_length: u32,
+    fingerprints_len: usize,
+) -> bool {
+    if fingerprints_len == 0 {
+        // An empty filter, as constructed from no keys.
+        return segment_length == 0 && segment_length_mask == 0 && segment_count_length == 0;
+    }
+    segment_length.is_power_of_two()
+        && segment_length_mask == segment_length - 1
+        && segment_count_length != 0
+        && segment_count_length & segment_length_mask == 0
+        && fingerprints_len as u64 == segment_count_length as u64 + 2 * segment_length as u64
+}
+
+/// Scratch buffers used while constructing a binary fuse filter. Keeping a `Scratch` around
+/// between constructions avoids reallocating the buffers each time.
+#[derive(Debug, Clone, Default)]
+pub struct Scratch {
+    /// Whether to keep the buffers after construction. Otherwise the largest buffers are freed as
+    /// soon as they are no longer needed.
+    pub retain: bool,
+    pub alone: Vec<u32>,
+    pub t2count: Vec<u8>,
+    pub t2hash: Vec<u64>,
+    pub reverse_h: Vec<u8>,
+    pub reverse_order: Vec<u64>,
+    pub start_pos: Vec<usize>,
+    /// Statistics about the last successful construction.
+    pub stats: crate::BuildStats,
+}
+
+impl Scratch {
+    /// Zeroes the buffers and sizes them for a filter of `size` keys, `capacity` fingerprints,
+    /// and `start_pos_len` segment start positions.
+    pub fn reset(&mut self, size: usize, capacity: usize, start_pos_len: usize) {
+        fn reset_block<T: Clone + Default>(block: &mut Vec<T>, len: usize) {
+            if block.capacity() < len {
+                // A freshly zeroed allocation is cheaper than zeroing an existing one.
+                *block = vec![T::default(); len];
+            } else {
+                block.clear();
+                block.resize(len, T::default());
+            }
+        }
+        reset_block(&mut self.alone, capacity);
+        reset_block(&mut self.t2count, capacity);
+        reset_block(&mut self.t2hash, capacity);
+        reset_block(&mut self.reverse_h, size);
+        reset_block(&mut self.reverse_order, size + 1);
+        reset_block(&mut self.start_pos, start_pos_len);
+    }
+}
+
+/// Hints to the CPU that `slice[index]` will be read soon. Compiles to nothing on targets without
+/// a prefetch instruction.
+#[inline(always)]
+#[allow(unused_variables)]
+pub fn prefetch<T>(slice: &[T], index: usize) {
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse"))]
+    {
+        use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        let ptr = slice.as_ptr().wrapping_add(index) as *const i8;
+        // SAFETY: prefetching is a hint that never faults, even for an invalid address.
+        unsafe { _mm_prefetch::<_MM_HINT_T0>(ptr) }
+    }
+    #[cfg(all(target_arch = "x86", target_feature = "sse"))]
+    {
+        use core::arch::x86::{_mm_prefetch, _MM_HINT_T0};
+        let ptr = slice.as_ptr().wrapping_add(index) as *const i8;
+        // SAFETY: prefetching is a hint that never faults, even for an invalid address.
+        unsafe { _mm_prefetch::<_MM_HINT_T0>(ptr) }
+    }
+}
+
 #[inline]
 pub const fn mod3(x: u8) -> u8 {
     if x > 2 {
@@ -53,65 +182,195 @@ pub const fn mod3(x: u8) -> u8 {
     }
 }
 
+/// The shape of the fingerprint array of a binary fuse filter.
+pub struct Dimensions {
+    pub segment_length: u32,
+    pub segment_count: u32,
+    pub fp_array_len: usize,
+}
+
+/// Sizes the fingerprint array of a binary fuse filter of `size` keys, with segments of at most
+/// `max_segment_length` slots.
+pub fn dimensions(size: usize, max_segment_length: u32) -> Result<Dimensions, crate::BuildError> {
+    let arity = 3u32;
+    // Fingerprints are indexed with 32-bit integers, so very large key sets are rejected below
+    // rather than silently truncated.
+    let size_u32: u32 = size.min(u32::MAX as usize) as u32;
+    let segment_length: u32 = segment_length(arity, size_u32).min(max_segment_length);
+    let size_factor: f64 = size_factor(arity, size_u32);
+    let capacity: u64 = if size > 1 {
+        round(size as f64 * size_factor) as u64
+    } else {
+        0
+    };
+    if capacity > u32::MAX as u64 {
+        return Err(crate::BuildError::TooManyKeys);
+    }
+    let init_segment_count = capacity.div_ceil(segment_length as u64);
+    let array_len = init_segment_count * segment_length as u64;
+    let segment_count: u64 = {
+        let proposed = array_len.div_ceil(segment_length as u64);
+        if proposed < arity as u64 {
+            1
+        } else {
+            proposed - (arity as u64 - 1)
+        }
+    };
+    let array_len: u64 = (segment_count + arity as u64 - 1) * segment_length as u64;
+    if array_len > u32::MAX as u64 {
+        return Err(crate::BuildError::TooManyKeys);
+    }
+    Ok(Dimensions {
+        segment_length,
+        segment_count: segment_count as u32,
+        fp_array_len: array_len as usize,
+    })
+}
+
+/// The largest segment length of a binary fuse filter constructed with default options.
+pub const MAX_SEGMENT_LENGTH: u32 = 262144;
+
 /// Implements `try_from(&[u64])` for an binary fuse filter of fingerprint type `$fpty`.
 #[doc(hidden)]
 #[macro_export]
 macro_rules! bfuse_from_impl(
     ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr) => {
+        $crate::bfuse_from_impl!($keys fingerprint $fpty, max iter $max_iter, rng seed 1)
+    };
+    ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr, rng seed $rng_seed:expr) => {
+        $crate::bfuse_from_impl!(
+            $keys fingerprint $fpty, max iter $max_iter, rng seed $rng_seed,
+            randomize cfg!(feature = "uniform-random")
+        )
+    };
+    ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr, rng seed $rng_seed:expr, randomize $randomize:expr) => {
+        $crate::bfuse_from_impl!(
+            $keys fingerprint $fpty, max iter $max_iter, rng seed $rng_seed,
+            randomize $randomize, scratch &mut $crate::prelude::bfuse::Scratch::default(),
+            max segment length $crate::prelude::bfuse::MAX_SEGMENT_LENGTH
+        )
+    };
+    ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr, rng seed $rng_seed:expr, randomize $randomize:expr, scratch $scratch:expr, max segment length $max_segment_length:expr) => {
+        $crate::bfuse_from_impl!(
+            @build $keys fingerprint $fpty, max iter $max_iter, rng seed $rng_seed,
+            fingerprints |size: usize, seed: u64| -> Box<[$fpty]> {
+                $crate::make_fp_block!(size, randomize $randomize, seed seed)
+            },
+            randomized $randomize,
+            all distinct $crate::prelude::all_distinct($keys.clone()),
+            hashes |seed, _buffer| $keys.clone().map(move |key| $crate::prelude::mix(key, seed)),
+            scratch $scratch, max segment length $max_segment_length, on attempt |_: usize| {}
+        )
+    };
+    ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr, rng seed $rng_seed:expr, fill $fill:expr) => {
+        $crate::bfuse_from_impl!(
+            $keys fingerprint $fpty, max iter $max_iter, rng seed $rng_seed,
+            fill $fill, scratch &mut $crate::prelude::bfuse::Scratch::default(),
+            max segment length $crate::prelude::bfuse::MAX_SEGMENT_LENGTH
+        )
+    };
+    ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr, rng seed $rng_seed:expr, fill $fill:expr, scratch $scratch:expr, max segment length $max_segment_length:expr) => {
         {
-            use libm::round;
-            use $crate::{
-                fingerprint,
-                make_block,
-                make_fp_block,
-                prelude::{
-                    mix,
-                    bfuse::{segment_length, size_factor, hash_of_hash, mod3},
+            let fill: Option<&mut dyn $crate::FillRng> = $fill;
+            let randomized = fill.is_some();
+            $crate::bfuse_from_impl!(
+                @build $keys fingerprint $fpty, max iter $max_iter, rng seed $rng_seed,
+                fingerprints move |size: usize, _: u64| -> Box<[$fpty]> {
+                    $crate::make_fp_block!(size, fill fill)
                 },
+                randomized randomized,
+                all distinct $crate::prelude::all_distinct($keys.clone()),
+                hashes |seed, _buffer| $keys.clone().map(move |key| $crate::prelude::mix(key, seed)),
+                scratch $scratch, max segment length $max_segment_length, on attempt |_: usize| {}
+            )
+        }
+    };
+    // Calls `on_attempt` with the number of each construction attempt as it starts.
+    ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr, rng seed $rng_seed:expr, on attempt $on_attempt:expr) => {
+        $crate::bfuse_from_impl!(
+            @build $keys fingerprint $fpty, max iter $max_iter, rng seed $rng_seed,
+            fingerprints |size: usize, seed: u64| -> Box<[$fpty]> {
+                $crate::make_fp_block!(size, seed seed)
+            },
+            randomized cfg!(feature = "uniform-random"),
+            all distinct $crate::prelude::all_distinct($keys.clone()),
+            hashes |seed, _buffer| $keys.clone().map(move |key| $crate::prelude::mix(key, seed)),
+            scratch &mut $crate::prelude::bfuse::Scratch::default(),
+            max segment length $crate::prelude::bfuse::MAX_SEGMENT_LENGTH,
+            on attempt $on_attempt
+        )
+    };
+    // Skips checking that the keys are distinct, for callers that have checked it already.
+    ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr, rng seed $rng_seed:expr, distinct) => {
+        $crate::bfuse_from_impl!(
+            @build $keys fingerprint $fpty, max iter $max_iter, rng seed $rng_seed,
+            fingerprints |size: usize, seed: u64| -> Box<[$fpty]> {
+                $crate::make_fp_block!(size, seed seed)
+            },
+            randomized cfg!(feature = "uniform-random"),
+            all distinct true,
+            hashes |seed, _buffer| $keys.clone().map(move |key| $crate::prelude::mix(key, seed)),
+            scratch &mut $crate::prelude::bfuse::Scratch::default(),
+            max segment length $crate::prelude::bfuse::MAX_SEGMENT_LENGTH, on attempt |_: usize| {}
+        )
+    };
+    // Hashes the keys on the rayon thread pool, into a buffer reused across attempts. Keys are
+    // placed into their segments serially in key order: a key whose segment is full spills into
+    // the next one, so placement depends on the order of the keys, and placing them serially keeps
+    // the result identical to the serial build.
+    ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr, rng seed $rng_seed:expr, parallel) => {
+        $crate::bfuse_from_impl!(
+            @build $keys fingerprint $fpty, max iter $max_iter, rng seed $rng_seed,
+            fingerprints |size: usize, seed: u64| -> Box<[$fpty]> {
+                $crate::make_fp_block!(size, seed seed)
+            },
+            randomized cfg!(feature = "uniform-random"),
+            all distinct $crate::prelude::all_distinct($keys.clone().collect::<Vec<u64>>()),
+            hashes |seed, buffer| {
+                use rayon::iter::ParallelExtend;
+                buffer.clear();
+                buffer.par_extend($keys.clone().map(move |key| $crate::prelude::mix(key, seed)));
+                buffer.iter().copied()
+            },
+            scratch &mut $crate::prelude::bfuse::Scratch::default(),
+            max segment length $crate::prelude::bfuse::MAX_SEGMENT_LENGTH, on attempt |_: usize| {}
+        )
+    };
+    (@build $keys:ident fingerprint $fpty:ty, max iter $max_iter:expr, rng seed $rng_seed:expr,
+     fingerprints $fingerprints:expr, randomized $randomized:expr,
+     all distinct $all_distinct:expr, hashes |$seed:ident, $buffer:ident| $hashes:expr,
+     scratch $scratch:expr, max segment length $max_segment_length:expr,
+     on attempt $on_attempt:expr) => {
+        {
+            use $crate::{
+                Fingerprint,
+                prelude::bfuse::{dimensions, Dimensions, hash_of_hash, mod3},
                 splitmix64::splitmix64,
             };
 
-            #[cfg(debug_assertions)] {
-                use $crate::prelude::all_distinct;
-                debug_assert!(all_distinct($keys.clone()), "Binary Fuse filters must be constructed from a collection containing all distinct keys.");
-            }
+            let scratch: &mut $crate::prelude::bfuse::Scratch = $scratch;
+            let on_attempt: &mut dyn FnMut(usize) = &mut $on_attempt;
+            scratch.stats = $crate::BuildStats::default();
 
-            let arity = 3u32;
             let size: usize = $keys.len();
-            let segment_length: u32 = segment_length(arity, size as u32).min(262144);
+            if size == 0 {
+                return Ok(Self::default());
+            }
+            let Dimensions { segment_length, segment_count, fp_array_len } =
+                dimensions(size, $max_segment_length)?;
             let segment_length_mask: u32 = segment_length - 1;
-            let size_factor: f64 = size_factor(arity, size as u32);
-            let capacity: u32 = if size > 1 {
-                round(size as f64 * size_factor) as u32
-            } else { 0 };
-            let init_segment_count = (capacity + segment_length - 1) / segment_length;
-            let (fp_array_len, segment_count) = {
-                let array_len = init_segment_count * segment_length;
-                let segment_count: u32 = {
-                    let proposed = (array_len + segment_length - 1) / segment_length;
-                    if proposed < arity {
-                        1
-                    } else {
-                        proposed - (arity - 1)
-                    }
-                };
-                let array_len: u32 = (segment_count + arity - 1) * segment_length;
-                (array_len as usize, segment_count)
-            };
-            let segment_count_length = segment_count * segment_length;
 
-            let mut fingerprints: Box<[$fpty]> = make_fp_block!(fp_array_len);
+            #[cfg(debug_assertions)] {
+                debug_assert!($all_distinct, "Binary Fuse filters must be constructed from a collection containing all distinct keys.");
+            }
+
+            let segment_count_length = segment_count * segment_length;
 
-            let mut rng = 1;
+            // Arms that collect the hashes of each attempt do so into this buffer.
+            let mut hash_buffer: Vec<u64> = Vec::new();
+            let mut rng: u64 = $rng_seed;
             let mut seed = splitmix64(&mut rng);
-            let capacity = fingerprints.len();
-            let mut alone: Box<[u32]> = make_block!(with capacity sets);
-            let mut t2count: Box<[u8]> = make_block!(with capacity sets);
-            let mut t2hash: Box<[u64]> = make_block!(with capacity sets);
-            let mut reverse_h: Box<[u8]> = make_block!(with size sets);
-            let size_plus_1: usize = size + 1;
-            let mut reverse_order: Box<[u64]> = make_block!(with size_plus_1 sets);
-            reverse_order[size] = 1;
+            let capacity = fp_array_len;
 
             let block_bits = {
                 let mut block_bits = 1;
@@ -120,18 +379,39 @@ macro_rules! bfuse_from_impl(
                 }
                 block_bits
             };
-
             let start_pos_len: usize = 1 << block_bits;
-            let mut start_pos: Box<[usize]> = make_block!(with start_pos_len sets);
+
+            scratch.reset(size, capacity, start_pos_len);
+            let alone: &mut [u32] = &mut scratch.alone;
+            let t2count: &mut [u8] = &mut scratch.t2count;
+            let t2hash: &mut [u64] = &mut scratch.t2hash;
+            let reverse_h: &mut [u8] = &mut scratch.reverse_h;
+            let reverse_order: &mut [u64] = &mut scratch.reverse_order;
+            reverse_order[size] = 1;
+            let start_pos: &mut [usize] = &mut scratch.start_pos;
             let mut h012: [u32; 6] = [0; 6];
             let mut done = false;
             let mut ultimate_size = 0;
-            for _ in 0..$max_iter {
+            for attempt in 0..$max_iter {
+                if attempt > 0 {
+                    // The previous attempt failed; reset for a retry with a new seed.
+                    for i in 0..size {
+                        reverse_order[i] = 0;
+                    }
+                    for i in 0..capacity {
+                        t2count[i] = 0;
+                        t2hash[i] = 0;
+                    }
+                    seed = splitmix64(&mut rng)
+                }
+                scratch.stats.attempts += 1;
+                on_attempt(scratch.stats.attempts);
                 for i in 0..start_pos_len {
                     start_pos[i] = (((i as u64) * (size as u64)) >> block_bits) as usize;
                 }
-                for key in $keys.clone() {
-                    let hash = mix(key, seed);
+                let $seed = seed;
+                let $buffer = &mut hash_buffer;
+                for hash in $hashes {
                     let mut segment_index = hash >> (64 - block_bits);
                     while reverse_order[start_pos[segment_index as usize] as usize] != 0 {
                         segment_index += 1;
@@ -147,6 +427,17 @@ macro_rules! bfuse_from_impl(
                     let hash = reverse_order[i];
                     let (index1, index2, index3) = hash_of_hash(hash, segment_length, segment_length_mask, segment_count_length);
                     let (index1, index2, index3) = (index1 as usize, index2 as usize, index3 as usize);
+                    // A slot counts its keys in the upper six bits of `t2count`, so a slot can hold
+                    // at most 63 keys. Random keys essentially never reach this, but keys chosen
+                    // against a known seed can; such an attempt fails and is retried with the next
+                    // seed rather than letting the count wrap around and corrupt the filter.
+                    if t2count[index1] > u8::MAX - 4
+                        || t2count[index2] > u8::MAX - 4
+                        || t2count[index3] > u8::MAX - 4
+                    {
+                        error = true;
+                        break;
+                    }
                     t2count[index1] += 4;
                     // t2count[index1] ^= 0; NOOP
                     t2hash[index1] ^= hash;
@@ -173,7 +464,6 @@ macro_rules! bfuse_from_impl(
 					                      t2hash[index3] ^= hash;
                         }
                     }
-                    error = t2count[index1] < 4 || t2count[index2] < 4 || t2count[index3] < 4;
                 }
                 if error {
                     continue;
@@ -227,30 +517,34 @@ macro_rules! bfuse_from_impl(
                 }
 
                 if stack_size + duplicates == size {
+                    scratch.stats.duplicates = duplicates;
+                    scratch.stats.final_seed = seed;
                     ultimate_size = stack_size;
                     done = true;
                     break
                 }
-
-                // Filter failed to be created; reset for a retry.
-                for i in 0..size {
-                    reverse_order[i] = 0;
-                }
-                for i in 0..capacity {
-                    t2count[i] = 0;
-                    t2hash[i] = 0;
-                }
-                seed = splitmix64(&mut rng)
             }
             if !done {
-                return Err("Failed to construct binary fuse filter.");
+                return Err($crate::BuildError::ConstructionFailed);
+            }
+
+            // The peeling buffers are no longer needed, so release them before allocating the
+            // fingerprints to lower peak memory.
+            if !scratch.retain {
+                scratch.alone = Vec::new();
+                scratch.t2count = Vec::new();
+                scratch.t2hash = Vec::new();
             }
+            let reverse_h: &[u8] = &scratch.reverse_h;
+            let reverse_order: &[u64] = &scratch.reverse_order;
+            let make_fingerprints = $fingerprints;
+            let mut fingerprints: Box<[$fpty]> = make_fingerprints(fp_array_len, seed);
 
             // Construct all fingerprints
             let size = ultimate_size;
             for i in (0..size).rev() {
                 let hash = reverse_order[i];
-                let xor2 = (fingerprint!(hash) as $fpty);
+                let xor2 = <$fpty as Fingerprint>::from_hash(hash);
                 let (index1, index2, index3) = hash_of_hash(hash, segment_length, segment_length_mask, segment_count_length);
                 let found = reverse_h[i] as usize;
 		            h012[0] = index1;
@@ -269,6 +563,8 @@ macro_rules! bfuse_from_impl(
                 segment_length,
                 segment_length_mask,
                 segment_count_length,
+                empty_slots_randomized: $randomized,
+                key_count: Some(size),
                 fingerprints,
             })
         }
@@ -280,21 +576,113 @@ macro_rules! bfuse_from_impl(
 #[macro_export]
 macro_rules! bfuse_contains_impl(
     ($key:expr, $self:expr, fingerprint $fpty:ty) => {
+        $crate::bfuse_contains_impl!(mixed $crate::prelude::mix($key, $self.seed), $self, fingerprint $fpty)
+    };
+    (mixed $hash:expr, $self:expr, fingerprint $fpty:ty) => {
+        {
+            use $crate::{Fingerprint, prelude::bfuse::hash_of_hash};
+            if $self.fingerprints.is_empty() {
+                false
+            } else {
+                let hash: u64 = $hash;
+                let f = <$fpty as Fingerprint>::from_hash(hash);
+                let (h0, h1, h2) = hash_of_hash(hash, $self.segment_length, $self.segment_length_mask, $self.segment_count_length);
+                f == $self.fingerprints[h0 as usize]
+                   ^ $self.fingerprints[h1 as usize]
+                   ^ $self.fingerprints[h2 as usize]
+            }
+        }
+    };
+);
+
+/// Implements `contains_simd(&[u64])` for a binary fuse filter of fingerprint type `$fpty`.
+#[cfg(feature = "simd")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! bfuse_contains_simd_impl(
+    ($keys:expr, $self:expr, fingerprint $fpty:ty) => {
+        {
+            use $crate::{
+                bfuse_contains_impl,
+                prelude::bfuse::{hash_of_hash_x4, LANES},
+            };
+            let keys: &[u64] = $keys;
+            if $self.fingerprints.is_empty() {
+                return vec![false; keys.len()];
+            }
+            let mut out = Vec::with_capacity(keys.len());
+            let mut chunks = keys.chunks_exact(LANES);
+            for chunk in &mut chunks {
+                let (fps, h0, h1, h2) = hash_of_hash_x4(
+                    chunk,
+                    $self.seed,
+                    $self.segment_length,
+                    $self.segment_length_mask,
+                    $self.segment_count_length,
+                );
+                for i in 0..LANES {
+                    let f = fps[i] as $fpty
+                        ^ $self.fingerprints[h0[i] as usize]
+                        ^ $self.fingerprints[h1[i] as usize]
+                        ^ $self.fingerprints[h2[i] as usize];
+                    out.push(f == 0);
+                }
+            }
+            for key in chunks.remainder() {
+                out.push(bfuse_contains_impl!(*key, $self, fingerprint $fpty));
+            }
+            out
+        }
+    };
+);
+
+/// Implements `contains_many(&[u64], &mut [bool])` for a binary fuse filter of fingerprint type
+/// `$fpty`.
+///
+/// Keys of filters too large to be cached are looked up in small batches: the slots of every key
+/// in a batch are prefetched before any of them is read, so that the cache misses of a batch
+/// overlap rather than being paid one after another.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! bfuse_contains_many_impl(
+    ($keys:expr, $out:expr, $self:expr, fingerprint $fpty:ty) => {
         {
             use $crate::{
-                fingerprint,
+                Fingerprint,
                 prelude::{
                     mix,
-                    bfuse::hash_of_hash
+                    bfuse::{hash_of_hash, prefetch},
                 },
             };
-            let hash = mix($key, $self.seed);
-            let mut f = fingerprint!(hash) as $fpty;
-            let (h0, h1, h2) = hash_of_hash(hash, $self.segment_length, $self.segment_length_mask, $self.segment_count_length);
-            f ^= $self.fingerprints[h0 as usize]
-               ^ $self.fingerprints[h1 as usize]
-               ^ $self.fingerprints[h2 as usize];
-            f == 0
+            const BATCH_SIZE: usize = 16;
+            // Filters smaller than this are likely to be cached, where prefetching only adds work.
+            const MIN_PREFETCH_BYTES: usize = 1 << 21;
+
+            let (keys, out): (&[u64], &mut [bool]) = ($keys, $out);
+            assert_eq!(keys.len(), out.len(), "keys and out must have the same length");
+            if core::mem::size_of_val(&*$self.fingerprints) < MIN_PREFETCH_BYTES {
+                for (key, out) in keys.iter().zip(out.iter_mut()) {
+                    *out = $crate::bfuse_contains_impl!(*key, $self, fingerprint $fpty);
+                }
+                return;
+            }
+            for (keys, out) in keys.chunks(BATCH_SIZE).zip(out.chunks_mut(BATCH_SIZE)) {
+                let mut slots = [(0u64, 0u32, 0u32, 0u32); BATCH_SIZE];
+                for (slot, &key) in slots.iter_mut().zip(keys) {
+                    let hash = mix(key, $self.seed);
+                    let (h0, h1, h2) = hash_of_hash(hash, $self.segment_length, $self.segment_length_mask, $self.segment_count_length);
+                    prefetch(&$self.fingerprints, h0 as usize);
+                    prefetch(&$self.fingerprints, h1 as usize);
+                    prefetch(&$self.fingerprints, h2 as usize);
+                    *slot = (hash, h0, h1, h2);
+                }
+                for (out, &(hash, h0, h1, h2)) in out.iter_mut().zip(&slots) {
+                    *out = <$fpty as Fingerprint>::from_hash(hash)
+                        == $self.fingerprints[h0 as usize]
+                            ^ $self.fingerprints[h1 as usize]
+                            ^ $self.fingerprints[h2 as usize];
+                }
+            }
         }
     };
 );
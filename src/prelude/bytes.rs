@@ -0,0 +1,246 @@
+//! Common methods for the `to_bytes`/`from_bytes` filter format.
+//!
+//! A serialized filter is laid out as
+//!
+//! - the magic bytes `b"XORF"`,
+//! - the format version, a `u8`,
+//! - the filter kind, a `u8`,
+//! - the number of bits in a fingerprint, a `u8`,
+//! - the filter's parameters, as little-endian integers,
+//! - the number of fingerprints, a little-endian `u64`,
+//! - the fingerprints, each as little-endian bytes.
+//!
+//! Since format version 2, the parameters are followed by the number of keys the filter was
+//! constructed from, a little-endian `u64` that is `u64::MAX` if the count is unknown. Binary Fuse
+//! filters are written in format version 3, which follows the key count with a flags byte whose
+//! lowest bit is set if the filter's empty slots are randomized. Xor filters are written in format
+//! version 2. Readers accept earlier versions of a filter kind's format too.
+
+use crate::Fingerprint;
+use alloc::{boxed::Box, vec::Vec};
+use core::convert::TryInto;
+
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+const MAGIC: [u8; 4] = *b"XORF";
+
+/// Filter kind of an [`Xor`](crate::Xor) filter.
+pub const XOR: u8 = 0;
+/// Filter kind of a Binary Fuse filter.
+#[cfg(feature = "binary-fuse")]
+pub const BINARY_FUSE: u8 = 1;
+
+/// Returns the format version filters of `kind` are written in.
+const fn version(kind: u8) -> u8 {
+    #[cfg(feature = "binary-fuse")]
+    if kind == BINARY_FUSE {
+        return 3;
+    }
+    let _ = kind;
+    2
+}
+
+/// Checks the header of a filter of `kind` with fingerprints of type `F`, after the magic bytes,
+/// returning its format version.
+fn check_header<F: Fingerprint>(header: &[u8], kind: u8) -> Option<u8> {
+    match *header {
+        [v, k, bits] if (1..=version(kind)).contains(&v) && k == kind && bits == F::BITS as u8 => {
+            Some(v)
+        }
+        _ => None,
+    }
+}
+
+/// Writes the header of a filter of `kind` with fingerprints of type `F`.
+pub fn header<F: Fingerprint>(kind: u8) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&[version(kind), kind, F::BITS as u8]);
+    bytes
+}
+
+/// Writes `fingerprints`, preceded by their count.
+pub fn write_fingerprints<F: Fingerprint>(bytes: &mut Vec<u8>, fingerprints: &[F]) {
+    bytes.extend_from_slice(&(fingerprints.len() as u64).to_le_bytes());
+    bytes.reserve(fingerprints.len() * (F::BITS / 8) as usize);
+    for &fingerprint in fingerprints {
+        fingerprint.write_le_bytes(bytes);
+    }
+}
+
+/// Encodes the number of keys a filter was constructed from, if known.
+pub fn key_count_bytes(key_count: Option<usize>) -> [u8; 8] {
+    key_count
+        .map_or(u64::MAX, |count| count as u64)
+        .to_le_bytes()
+}
+
+/// Decodes a key count encoded with [`key_count_bytes`]. Returns an error if the count doesn't fit
+/// in a `usize`.
+pub fn read_key_count(key_count: u64) -> Result<Option<usize>, ()> {
+    if key_count == u64::MAX {
+        return Ok(None);
+    }
+    usize::try_from(key_count).map(Some).map_err(|_| ())
+}
+
+/// Reads a serialized filter.
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    /// The format version of the filter.
+    pub version: u8,
+}
+
+impl<'a> Reader<'a> {
+    /// Reads the header of a filter of `kind` with fingerprints of type `F`.
+    pub fn new<F: Fingerprint>(bytes: &'a [u8], kind: u8) -> Result<Self, &'static str> {
+        let mut reader = Self { bytes, version: 0 };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err("Filter bytes are missing the xorf header.");
+        }
+        reader.version = check_header::<F>(reader.take(3)?, kind)
+            .ok_or("Filter bytes are of a different filter type or format version.")?;
+        Ok(reader)
+    }
+
+    const fn take(&mut self, len: usize) -> Result<&'a [u8], &'static str> {
+        if self.bytes.len() < len {
+            return Err("Filter bytes are truncated.");
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    /// Reads a `u8`.
+    #[cfg(feature = "binary-fuse")]
+    pub fn u8(&mut self) -> Result<u8, &'static str> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads a little-endian `u32`.
+    #[cfg(feature = "binary-fuse")]
+    pub fn u32(&mut self) -> Result<u32, &'static str> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// Reads a little-endian `u64`.
+    pub fn u64(&mut self) -> Result<u64, &'static str> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// Reads the fingerprints, which must be the last thing in the filter bytes.
+    pub fn fingerprints<F: Fingerprint>(mut self) -> Result<Box<[F]>, &'static str> {
+        let width = (F::BITS / 8) as usize;
+        let len = self.u64()?;
+        if len.checked_mul(width as u64) != Some(self.bytes.len() as u64) {
+            return Err("Filter bytes have the wrong number of fingerprints.");
+        }
+        Ok(self
+            .bytes
+            .chunks_exact(width)
+            .map(F::read_le_bytes)
+            .collect())
+    }
+}
+
+/// The number of fingerprints converted at a time when streaming fingerprints, which bounds the
+/// size of the conversion buffer.
+#[cfg(feature = "std")]
+const CHUNK_LEN: usize = 8192;
+
+/// Returns an [`io::Error`] for filter bytes that are not in the expected format.
+#[cfg(feature = "std")]
+pub fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Writes `fingerprints` to `w`, preceded by their count, as [`write_fingerprints`] does.
+#[cfg(feature = "std")]
+pub fn write_fingerprints_to<F: Fingerprint, W: Write>(
+    w: &mut W,
+    fingerprints: &[F],
+) -> io::Result<()> {
+    w.write_all(&(fingerprints.len() as u64).to_le_bytes())?;
+    let mut buf = Vec::with_capacity(CHUNK_LEN.min(fingerprints.len()) * (F::BITS / 8) as usize);
+    for chunk in fingerprints.chunks(CHUNK_LEN) {
+        buf.clear();
+        for &fingerprint in chunk {
+            fingerprint.write_le_bytes(&mut buf);
+        }
+        w.write_all(&buf)?;
+    }
+    Ok(())
+}
+
+/// Reads a serialized filter from a stream.
+#[cfg(feature = "std")]
+pub struct StreamReader<'a, R> {
+    r: &'a mut R,
+    /// The format version of the filter.
+    pub version: u8,
+}
+
+#[cfg(feature = "std")]
+impl<'a, R: Read> StreamReader<'a, R> {
+    /// Reads the header of a filter of `kind` with fingerprints of type `F`.
+    pub fn new<F: Fingerprint>(r: &'a mut R, kind: u8) -> io::Result<Self> {
+        let mut header = [0; 7];
+        r.read_exact(&mut header)?;
+        if header[..MAGIC.len()] != MAGIC {
+            return Err(invalid_data("Filter bytes are missing the xorf header."));
+        }
+        let version = check_header::<F>(&header[MAGIC.len()..], kind).ok_or_else(|| {
+            invalid_data("Filter bytes are of a different filter type or format version.")
+        })?;
+        Ok(Self { r, version })
+    }
+
+    /// Reads a `u8`.
+    #[cfg(feature = "binary-fuse")]
+    pub fn u8(&mut self) -> io::Result<u8> {
+        let mut bytes = [0; 1];
+        self.r.read_exact(&mut bytes)?;
+        Ok(bytes[0])
+    }
+
+    /// Reads a little-endian `u32`.
+    #[cfg(feature = "binary-fuse")]
+    pub fn u32(&mut self) -> io::Result<u32> {
+        let mut bytes = [0; 4];
+        self.r.read_exact(&mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    /// Reads a little-endian `u64`.
+    pub fn u64(&mut self) -> io::Result<u64> {
+        let mut bytes = [0; 8];
+        self.r.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    /// Reads the fingerprints, leaving anything after them in the stream unread.
+    pub fn fingerprints<F: Fingerprint>(mut self) -> io::Result<Box<[F]>> {
+        let width = (F::BITS / 8) as usize;
+        let len: usize = self
+            .u64()?
+            .try_into()
+            .map_err(|_| invalid_data("Filter bytes have the wrong number of fingerprints."))?;
+        let mut fingerprints = Vec::new();
+        // The count is untrusted, so a failure to allocate for it is reported rather than aborting.
+        fingerprints
+            .try_reserve_exact(len)
+            .map_err(|_| invalid_data("Filter bytes have the wrong number of fingerprints."))?;
+        let mut buf = vec![0; CHUNK_LEN.min(len) * width];
+        let mut remaining = len;
+        while remaining > 0 {
+            let chunk_len = CHUNK_LEN.min(remaining);
+            let chunk = &mut buf[..chunk_len * width];
+            self.r.read_exact(chunk)?;
+            fingerprints.extend(chunk.chunks_exact(width).map(F::read_le_bytes));
+            remaining -= chunk_len;
+        }
+        Ok(fingerprints.into_boxed_slice())
+    }
+}
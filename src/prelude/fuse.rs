@@ -2,26 +2,52 @@ use crate::prelude::HashSet;
 
 const H3: u64 = 0xBF58_476D_1CE4_E5B9;
 const ARITY: usize = 3;
-const SEGMENT_COUNT: usize = 100;
-pub const SLOTS: usize = SEGMENT_COUNT + ARITY - 1;
+/// The number of segments fuse filters are constructed with by default.
+pub const SEGMENT_COUNT: usize = 100;
 pub const FUSE_OVERHEAD: f64 = 1.0 / 0.879;
 
 impl HashSet {
-    pub const fn fuse_from(key: u64, segment_length: usize, seed: u64) -> Self {
+    pub const fn fuse_from(
+        key: u64,
+        segment_length: usize,
+        segment_count: usize,
+        seed: u64,
+    ) -> Self {
         let hash = crate::prelude::mix(key, seed);
-        let H012 { hset } = H012::from(hash, segment_length);
+        let H012 { hset } = H012::from(hash, segment_length, segment_count);
 
         Self { hash, hset }
     }
 }
 
+/// Returns the number of slots in a fuse filter with `segment_count` segments, in units of the
+/// segment length. Each key spans `ARITY` consecutive segments, so the last segment a key can start
+/// in is followed by `ARITY - 1` more.
+pub const fn slots(segment_count: usize) -> usize {
+    segment_count + ARITY - 1
+}
+
+/// Returns the number of segments of a fuse filter with `len` fingerprints of which each segment
+/// holds `segment_length`. Filters do not store their segment count, since it is determined by
+/// these two.
+pub const fn segment_count(len: usize, segment_length: usize) -> usize {
+    (len / segment_length).saturating_sub(ARITY - 1)
+}
+
+/// Estimates the number of keys a fuse filter with `len` fingerprints was constructed from, by
+/// inverting `FUSE_OVERHEAD`. Filters are rounded down to a whole number of slots, which is
+/// ignored here.
+pub fn estimated_capacity(len: usize) -> usize {
+    (len as f64 / FUSE_OVERHEAD + 0.5) as usize
+}
+
 /// Just the indexing hashes of a key.
 pub struct H012 {
     pub hset: [usize; 3],
 }
 
 impl H012 {
-    pub const fn from(hash: u64, segment_length: usize) -> Self {
+    pub const fn from(hash: u64, segment_length: usize, segment_count: usize) -> Self {
         use crate::{reduce, rotl64};
 
         let r0 = hash as u32;
@@ -29,7 +55,7 @@ impl H012 {
         let r2 = rotl64!(hash, by 42) as u32;
         let r3 = ((H3.overflowing_mul(hash).0) >> 32) as u32;
 
-        let seg = reduce!(r0 on interval SEGMENT_COUNT);
+        let seg = reduce!(r0 on interval segment_count);
 
         Self {
             hset: [
@@ -47,17 +73,23 @@ impl H012 {
 macro_rules! fuse_contains_impl(
     ($key:expr, $self:expr, fingerprint $fpty:ty) => {
         {
-            use $crate::prelude::HashSet;
-
-            let HashSet {
-                hash,
-                hset: [h0, h1, h2],
-            } = HashSet::fuse_from($key, $self.segment_length, $self.seed);
-            let fp = $crate::fingerprint!(hash) as $fpty;
-
-            fp == $self.fingerprints[h0]
-                ^ $self.fingerprints[h1]
-                ^ $self.fingerprints[h2]
+            use $crate::prelude::{fuse::segment_count, HashSet};
+
+            // An empty filter has no segments to reduce hashes onto.
+            if $self.fingerprints.is_empty() || $self.segment_length == 0 {
+                false
+            } else {
+                let segment_count = segment_count($self.fingerprints.len(), $self.segment_length);
+                let HashSet {
+                    hash,
+                    hset: [h0, h1, h2],
+                } = HashSet::fuse_from($key, $self.segment_length, segment_count, $self.seed);
+                let fp = $crate::fingerprint!(hash) as $fpty;
+
+                fp == $self.fingerprints[h0]
+                    ^ $self.fingerprints[h1]
+                    ^ $self.fingerprints[h2]
+            }
         }
     };
 );
@@ -67,6 +99,12 @@ macro_rules! fuse_contains_impl(
 #[macro_export]
 macro_rules! fuse_from_impl(
     ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr) => {
+        $crate::fuse_from_impl!(
+            $keys fingerprint $fpty, max iter $max_iter,
+            segments $crate::prelude::fuse::SEGMENT_COUNT
+        )
+    };
+    ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr, segments $segment_count:expr) => {
         {
             use $crate::{
                 fingerprint,
@@ -74,7 +112,7 @@ macro_rules! fuse_from_impl(
                 make_fp_block,
                 prelude::{
                     HashSet, HSet, KeyIndex,
-                    fuse::{H012, FUSE_OVERHEAD, SLOTS},
+                    fuse::{slots, H012, FUSE_OVERHEAD},
                 },
                 splitmix64::splitmix64,
                 try_enqueue,
@@ -86,10 +124,19 @@ macro_rules! fuse_from_impl(
             }
 
             // See Algorithm 3 in the paper.
+            let segment_count: usize = $segment_count;
+            if segment_count == 0 {
+                return Err($crate::BuildError::ConstructionFailed);
+            }
             let num_keys = $keys.len();
+            if num_keys == 0 {
+                return Ok(Self::default());
+            }
+            let slots = slots(segment_count);
             let capacity = (FUSE_OVERHEAD * num_keys as f64) as usize;
-            let capacity = capacity / SLOTS * SLOTS;
-            let segment_length = capacity / SLOTS;
+            // Every segment holds at least one slot, so that small key sets can be placed.
+            let capacity = (capacity / slots * slots).max(slots);
+            let segment_length = capacity / slots;
 
             #[allow(non_snake_case)]
             let mut H: Box<[HSet]> = make_block!(with capacity sets);
@@ -103,7 +150,7 @@ macro_rules! fuse_from_impl(
             for _ in 0..$max_iter {
                 // Populate H by adding each key to its respective set.
                 for key in $keys.clone() {
-                    let HashSet { hash, hset } = HashSet::fuse_from(key, segment_length, seed);
+                    let HashSet { hash, hset } = HashSet::fuse_from(key, segment_length, segment_count, seed);
 
                     for b in 0..3 {
                         H[hset[b]].mask ^= hash;
@@ -126,7 +173,7 @@ macro_rules! fuse_from_impl(
                         continue
                     }
 
-                    let H012 { hset } = H012::from(ki.hash, segment_length);
+                    let H012 { hset } = H012::from(ki.hash, segment_length, segment_count);
 
                     stack[stack_size] = ki;
                     stack_size += 1;
@@ -153,14 +200,14 @@ macro_rules! fuse_from_impl(
             }
 
             if !done {
-                return Err("Failed to construct fuse filter.");
+                return Err($crate::BuildError::ConstructionFailed);
             }
 
             // Construct all fingerprints (see Algorithm 4 in the paper).
             #[allow(non_snake_case)]
-            let mut B: Box<[$fpty]> = make_fp_block!(capacity);
+            let mut B: Box<[$fpty]> = make_fp_block!(capacity, seed seed);
             for ki in stack.iter().rev() {
-                let H012 { hset: [h0, h1, h2] } = H012::from(ki.hash, segment_length);
+                let H012 { hset: [h0, h1, h2] } = H012::from(ki.hash, segment_length, segment_count);
                 let fp = (fingerprint!(ki.hash) as $fpty) ^ match ki.index {
                     h if h == h0 => B[h1] ^ B[h2],
                     h if h == h1 => B[h0] ^ B[h2],
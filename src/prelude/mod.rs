@@ -2,6 +2,7 @@
 
 #[cfg(feature = "binary-fuse")]
 pub mod bfuse;
+pub mod bytes;
 pub mod fuse;
 pub mod xor;
 
@@ -86,27 +87,38 @@ macro_rules! make_block(
 /// than if the unused entries are uniformly random
 ///
 /// Of course, the tradeoff here is that generating random elements is more expensive than
-/// memsetting a bunch of zeroes, so the option is configurable with the `uniform-random` feature.
+/// memsetting a bunch of zeroes, so the option is configurable with the `uniform-random` feature,
+/// and per filter with `randomize` or a `fill` generator.
+///
+/// Randomized fingerprints are drawn from a [`SplitMix64`](crate::SplitMix64) seeded with the
+/// filter's seed, so that constructing a filter from the same keys always yields the same filter.
 #[doc(hidden)]
 #[macro_export]
 macro_rules! make_fp_block(
-    ($size:ident) => {
+    ($size:ident, seed $seed:expr) => {
+        $crate::make_fp_block!($size, randomize cfg!(feature = "uniform-random"), seed $seed)
+    };
+    ($size:ident, randomize $randomize:expr, seed $seed:expr) => {
+        {
+            let mut rng = $crate::SplitMix64::new($seed);
+            $crate::make_fp_block!($size, fill if $randomize { Some(&mut rng) } else { None })
+        }
+    };
+    ($size:ident, fill $rng:expr) => {
         {
-            #[cfg(feature = "uniform-random")] {
-                use rand::Rng;
-                let mut rng = rand::thread_rng();
-                let mut block = Vec::with_capacity($size);
-                for _ in 0..$size {
-                    block.push(rng.gen());
+            let rng: Option<&mut dyn $crate::FillRng> = $rng;
+            match rng {
+                Some(rng) => {
+                    let mut block = Vec::with_capacity($size);
+                    for _ in 0..$size {
+                        block.push($crate::Fingerprint::from_hash(rng.next_u64()));
+                    }
+                    block.into_boxed_slice()
                 }
-                block.into_boxed_slice()
-            }
-
-            #[cfg(not(feature = "uniform-random"))] {
-                make_block!(with $size sets)
+                None => $crate::make_block!(with $size sets),
             }
         }
-    }
+    };
 );
 
 /// Creates a block of sets, each set being of type T.
@@ -123,9 +135,57 @@ macro_rules! try_enqueue(
     };
 );
 
+/// Formats the first few fingerprints of a filter followed by their number, as the `Debug`
+/// implementations of filters do. Printing every fingerprint of a large filter is unusable.
+pub struct FingerprintsPreview<'a, F>(pub &'a [F]);
+
+impl<F: core::fmt::Debug> core::fmt::Debug for FingerprintsPreview<'_, F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        const SHOWN: usize = 4;
+        let mut list = f.debug_list();
+        list.entries(self.0.iter().take(SHOWN));
+        if self.0.len() > SHOWN {
+            list.entry(&format_args!(".. len {} ..", self.0.len()));
+        }
+        list.finish()
+    }
+}
+
+/// Writes the one-line summary of a filter printed by its `Display` implementation, for example
+/// `Xor8 { fingerprints: 1230, estimated keys: 1000, bits/entry: 9.84, fp_rate: ~2^-8 }`.
+pub fn fmt_summary(
+    f: &mut core::fmt::Formatter<'_>,
+    name: &str,
+    bits: u32,
+    fingerprints: usize,
+    estimated_keys: usize,
+) -> core::fmt::Result {
+    let bits_per_entry = if estimated_keys == 0 {
+        0.0
+    } else {
+        fingerprints as f64 * f64::from(bits) / estimated_keys as f64
+    };
+    write!(
+        f,
+        "{}{} {{ fingerprints: {}, estimated keys: {}, bits/entry: {:.2}, fp_rate: ~2^-{} }}",
+        name, bits, fingerprints, estimated_keys, bits_per_entry, bits
+    )
+}
+
 /// Checks if a collection of keys has all distinct values.
-#[cfg(debug_assertions)]
+#[cfg(any(debug_assertions, feature = "binary-fuse"))]
 pub fn all_distinct(keys: impl IntoIterator<Item = u64>) -> bool {
     let mut s = alloc::collections::BTreeSet::new();
     keys.into_iter().all(move |x| s.insert(x))
 }
+
+/// Checks if a collection of keys is sorted in strictly ascending order, which means its values
+/// are all distinct, in a single pass.
+#[cfg(all(debug_assertions, feature = "binary-fuse"))]
+pub fn strictly_ascending(keys: impl IntoIterator<Item = u64>) -> bool {
+    let mut keys = keys.into_iter();
+    let Some(mut prev) = keys.next() else {
+        return true;
+    };
+    keys.all(move |x| core::mem::replace(&mut prev, x) < x)
+}
@@ -6,15 +6,44 @@ impl HashSet {
 
         Self {
             hash,
-            hset: [
-                crate::xor_h!(index block 0, of length block_length, using hash),
-                crate::xor_h!(index block 1, of length block_length, using hash),
-                crate::xor_h!(index block 2, of length block_length, using hash),
-            ],
+            hset: xor_indices(hash, block_length),
         }
     }
 }
 
+/// Computes the index of a key with hash `hash` within each of the three filter blocks.
+///
+/// Equivalent to `xor_h!` for blocks 0, 1, and 2, with the rotations by a constant written out
+/// so that each is a single rotate instruction.
+pub const fn xor_indices(hash: u64, block_length: usize) -> [usize; 3] {
+    let r0 = hash as u32;
+    let r1 = hash.rotate_left(21) as u32;
+    let r2 = hash.rotate_left(42) as u32;
+    [
+        crate::reduce!(r0 on interval block_length),
+        crate::reduce!(r1 on interval block_length),
+        crate::reduce!(r2 on interval block_length),
+    ]
+}
+
+/// Sizes the blocks of an xor filter of `num_keys` keys, which hold `1.23` slots per key plus a
+/// constant 32 slots between them, rounded down to a multiple of 3.
+pub fn block_length(num_keys: usize) -> Result<usize, crate::BuildError> {
+    // `1.23 * num_keys`, rounded down, computed with integers so that it neither loses precision
+    // nor overflows for large key sets.
+    let extra = 23 * (num_keys / 100) + 23 * (num_keys % 100) / 100;
+    let capacity = num_keys
+        .checked_add(extra)
+        .and_then(|capacity| capacity.checked_add(32))
+        .ok_or(crate::BuildError::TooManyKeys)?;
+    let block_length = capacity / 3;
+    // Hashes are reduced onto a block with a 32-bit multiply, which can't index larger blocks.
+    if block_length as u64 > 1 << 32 {
+        return Err(crate::BuildError::TooManyKeys);
+    }
+    Ok(block_length)
+}
+
 /// Computes a hash indexing the i'th filter block.
 #[doc(hidden)]
 #[macro_export]
@@ -32,30 +61,36 @@ macro_rules! xor_h(
 #[macro_export]
 macro_rules! xor_contains_impl(
     ($key:expr, $self:expr, fingerprint $fpty:ty) => {
+        $crate::xor_contains_impl!(mixed $crate::prelude::mix($key, $self.seed), $self, fingerprint $fpty)
+    };
+    (mixed $hash:expr, $self:expr, fingerprint $fpty:ty) => {
         {
-            use $crate::prelude::HashSet;
-
-            let HashSet {
-                hash,
-                hset: [h0, h1, h2],
-            } = HashSet::xor_from($key, $self.block_length, $self.seed);
-            let fp = $crate::fingerprint!(hash) as $fpty;
-
-            fp == $self.fingerprints[h0]
-                ^ $self.fingerprints[(h1 + $self.block_length)]
-                ^ $self.fingerprints[(h2 + 2 * $self.block_length)]
+            // An empty filter has no blocks to reduce hashes onto.
+            if $self.fingerprints.is_empty() || $self.block_length == 0 {
+                false
+            } else {
+                let hash: u64 = $hash;
+                let [h0, h1, h2] = $crate::prelude::xor::xor_indices(hash, $self.block_length);
+                let fp = <$fpty as $crate::Fingerprint>::from_hash(hash);
+
+                fp == $self.fingerprints[h0]
+                    ^ $self.fingerprints[(h1 + $self.block_length)]
+                    ^ $self.fingerprints[(h2 + 2 * $self.block_length)]
+            }
         }
     };
  );
 
-/// Creates an `from(&[u64])` implementation for an xor filter of fingerprint type `$fpty`.
+/// Creates an `try_from(&[u64])` implementation for an xor filter of fingerprint type `$fpty`.
 #[doc(hidden)]
 #[macro_export]
 macro_rules! xor_from_impl(
-    ($keys:ident fingerprint $fpty:ty) => {
+    ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr) => {
+        $crate::xor_from_impl!($keys fingerprint $fpty, max iter $max_iter, rng seed 1)
+    };
+    ($keys:ident fingerprint $fpty:ty, max iter $max_iter:expr, rng seed $rng_seed:expr) => {
         {
             use $crate::{
-                fingerprint,
                 xor_h,
                 make_block,
                 prelude::{HashSet, HSet, KeyIndex},
@@ -70,9 +105,11 @@ macro_rules! xor_from_impl(
 
             // See Algorithm 3 in the paper.
             let num_keys = $keys.len();
-            let capacity = (1.23 * num_keys as f64) as usize + 32;
-            let capacity = capacity / 3 * 3; // round to nearest multiple of 3
-            let block_length = capacity / 3;
+            if num_keys == 0 {
+                return Ok(Self::default());
+            }
+            let block_length = $crate::prelude::xor::block_length(num_keys)?;
+            let capacity = block_length * 3;
 
             #[allow(non_snake_case)]
             let mut H: [Box<[HSet]>; 3] = [
@@ -88,9 +125,10 @@ macro_rules! xor_from_impl(
             ];
             let mut stack: Box<[KeyIndex]> = make_block!(with num_keys sets);
 
-            let mut rng = 1;
+            let mut rng: u64 = $rng_seed;
             let mut seed = splitmix64(&mut rng);
-            loop {
+            let mut done = false;
+            for _ in 0..$max_iter {
                 // Populate H by adding each key to its respective set.
                 for key in $keys.clone() {
                     let HashSet { hash, hset } = HashSet::xor_from(key, block_length, seed);
@@ -148,6 +186,7 @@ macro_rules! xor_from_impl(
                 }
 
                 if stack_size == num_keys {
+                    done = true;
                     break;
                 }
 
@@ -160,21 +199,26 @@ macro_rules! xor_from_impl(
                 seed = splitmix64(&mut rng)
             }
 
+            if !done {
+                return Err($crate::BuildError::ConstructionFailed);
+            }
+
             // Construct all fingerprints (see Algorithm 4 in the paper).
             #[allow(non_snake_case)]
             let mut B: Box<[$fpty]> = make_block!(with capacity sets);
             for ki in stack.iter().rev() {
-                B[ki.index] = fingerprint!(ki.hash) as $fpty
+                B[ki.index] = <$fpty as $crate::Fingerprint>::from_hash(ki.hash)
                     ^ B[xor_h!(index block 0, of length block_length, using ki.hash)]
                     ^ B[(xor_h!(index block 1, of length block_length, using ki.hash) + block_length)]
                     ^ B[(xor_h!(index block 2, of length block_length, using ki.hash) + 2 * block_length)];
             }
 
-            Self {
+            Ok(Self {
                 seed,
                 block_length,
+                key_count: Some(num_keys),
                 fingerprints: B,
-            }
+            })
         }
     };
 );
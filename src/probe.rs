@@ -0,0 +1,103 @@
+//! Implements the result of probing a filter for a key.
+
+use crate::Fingerprint;
+
+/// The fingerprints compared when looking up a key in a filter, returned by [`Xor::probe`] and
+/// [`BinaryFuse::probe`] for debugging false positives.
+///
+/// A filter contains a key if the key's fingerprint equals the xor of the fingerprints stored in
+/// the slots the key hashes to. Both are widened to a `u64`.
+///
+/// ```
+/// # extern crate alloc;
+/// use xorf::{Filter, Xor8};
+/// # use alloc::vec::Vec;
+///
+/// let keys: Vec<u64> = (0..10_000).collect();
+/// let filter = Xor8::from(&keys);
+///
+/// let probe = filter.probe(&42);
+/// assert!(probe.matched);
+/// assert_eq!(probe.key_fingerprint, probe.stored_xor);
+/// ```
+///
+/// [`Xor::probe`]: crate::Xor::probe
+/// [`BinaryFuse::probe`]: crate::BinaryFuse::probe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Probe {
+    /// Whether the filter contains the key, the same as [`contains`](crate::Filter::contains).
+    pub matched: bool,
+    /// The fingerprint of the key.
+    pub key_fingerprint: u64,
+    /// The xor of the fingerprints stored in the slots the key hashes to, or zero if the filter
+    /// is empty.
+    pub stored_xor: u64,
+}
+
+impl Probe {
+    pub(crate) fn new<F: Fingerprint>(key_fingerprint: F, stored_xor: Option<F>) -> Self {
+        Self {
+            matched: stored_xor == Some(key_fingerprint),
+            key_fingerprint: key_fingerprint.into(),
+            stored_xor: stored_xor.map_or(0, Into::into),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Filter, Xor16};
+
+    use alloc::vec::Vec;
+    use rand::Rng;
+
+    #[test]
+    fn test_probe_agrees_with_contains() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        let filter = Xor16::from(&keys);
+
+        for key in keys.iter() {
+            let probe = filter.probe(key);
+            assert!(probe.matched);
+            assert_eq!(probe.key_fingerprint, probe.stored_xor);
+        }
+        for key in (0..SAMPLE_SIZE).map(|_| rng.gen()) {
+            let probe = filter.probe(&key);
+            assert_eq!(probe.matched, filter.contains(&key));
+            assert_eq!(probe.matched, probe.key_fingerprint == probe.stored_xor);
+            assert!(probe.key_fingerprint <= u16::MAX as u64);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "binary-fuse")]
+    fn test_binary_fuse_probe() {
+        use crate::BinaryFuse8;
+
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        let filter = BinaryFuse8::try_from_iterator(keys.iter().copied()).unwrap();
+
+        assert!(keys.iter().all(|key| filter.probe(key).matched));
+        let mut false_positives = 0;
+        for key in (0..SAMPLE_SIZE).map(|_| rng.gen()) {
+            let probe = filter.probe(&key);
+            assert_eq!(probe.matched, filter.contains(&key));
+            if probe.matched {
+                false_positives += 1;
+                assert_eq!(probe.key_fingerprint, probe.stored_xor);
+            }
+        }
+        assert!(false_positives < SAMPLE_SIZE / 100);
+    }
+
+    #[test]
+    fn test_empty() {
+        let probe = Xor16::default().probe(&1);
+        assert!(!probe.matched);
+        assert_eq!(probe.stored_xor, 0);
+    }
+}
@@ -0,0 +1,230 @@
+//! Implements a filter sharded over several independently constructed filters.
+
+use crate::Filter;
+use alloc::{boxed::Box, vec::Vec};
+use core::convert::TryFrom;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bincode")]
+use bincode::{Decode, Encode};
+
+/// A [`Filter`] over keys split between several shards, each a filter of its own.
+///
+/// A key is routed to a shard by its high bits, so that shard `i` of `n` holds the keys in
+/// `i * 2^64 / n..(i + 1) * 2^64 / n`, and only that shard is queried for the key. Keys should be
+/// uniformly distributed, like the hashes produced by a [`HashProxy`], for the shards to be of
+/// similar size.
+///
+/// Each shard can be rebuilt independently of the others, which keeps rebuilds of large key sets
+/// small.
+///
+/// ```
+/// # extern crate alloc;
+/// use xorf::{Filter, ShardedFilter, Xor8};
+/// # use alloc::vec::Vec;
+/// # use rand::Rng;
+///
+/// # let mut rng = rand::thread_rng();
+/// const SAMPLE_SIZE: usize = 100_000;
+/// let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+/// let mut filter: ShardedFilter<Xor8> = ShardedFilter::from_keys(&keys, 16);
+///
+/// for key in keys.iter() {
+///     assert!(filter.contains(key));
+/// }
+///
+/// // Rebuild the shard of a new key without touching the others.
+/// let key = rng.gen();
+/// let shard = ShardedFilter::<Xor8>::shard_index(key, 16);
+/// let mut shard_keys = ShardedFilter::<Xor8>::partition(&keys, 16).swap_remove(shard);
+/// shard_keys.push(key);
+/// filter.shards_mut()[shard] = Xor8::from(&shard_keys);
+/// assert!(filter.contains(&key));
+/// ```
+///
+/// Serializing and deserializing `ShardedFilter`s can be enabled with the [`serde`] feature.
+///
+/// [`HashProxy`]: crate::HashProxy
+/// [`serde`]: http://serde.rs
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "bincode", derive(Encode, Decode))]
+#[cfg_attr(
+    feature = "bincode",
+    bincode(
+        decode_bounds = "F: Decode<__Context> + 'static",
+        borrow_decode_bounds = "F: bincode::BorrowDecode<'__de, __Context> + '__de"
+    )
+)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShardedFilter<F> {
+    shards: Box<[F]>,
+}
+
+impl<F: Filter<u64>> Filter<u64> for ShardedFilter<F> {
+    /// Returns `true` if the shard `key` is routed to contains it. A `ShardedFilter` without
+    /// shards contains no keys.
+    #[inline]
+    fn contains(&self, key: &u64) -> bool {
+        !self.shards.is_empty()
+            && self.shards[Self::shard_index(*key, self.shards.len())].contains(key)
+    }
+
+    fn len(&self) -> usize {
+        self.shards.iter().map(Filter::len).sum()
+    }
+}
+
+impl<F: Filter<u64>> ShardedFilter<F> {
+    /// Construct the filter from its shards. The keys of `shards[i]` must be the keys routed to
+    /// shard `i` of `shards.len()`, such as `partition(keys, shards.len())[i]`.
+    pub fn from_shards(shards: Vec<F>) -> Self {
+        Self {
+            shards: shards.into_boxed_slice(),
+        }
+    }
+
+    /// Returns the shards of the filter.
+    pub const fn shards(&self) -> &[F] {
+        &self.shards
+    }
+
+    /// Returns the shards of the filter, so that a shard can be replaced with one rebuilt from
+    /// the keys routed to it.
+    pub fn shards_mut(&mut self) -> &mut [F] {
+        &mut self.shards
+    }
+
+    /// Consumes the filter, returning its shards.
+    pub fn into_shards(self) -> Vec<F> {
+        self.shards.into_vec()
+    }
+
+    /// Returns the index of the shard `key` is routed to, out of `num_shards` shards.
+    pub const fn shard_index(key: u64, num_shards: usize) -> usize {
+        ((key as u128 * num_shards as u128) >> 64) as usize
+    }
+
+    /// Splits `keys` into the keys routed to each of `num_shards` shards, in order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_shards` is zero.
+    pub fn partition(keys: &[u64], num_shards: usize) -> Vec<Vec<u64>> {
+        assert!(num_shards > 0, "A sharded filter needs at least one shard.");
+        let mut shards = vec![Vec::new(); num_shards];
+        for &key in keys {
+            shards[Self::shard_index(key, num_shards)].push(key);
+        }
+        shards
+    }
+}
+
+impl<F> ShardedFilter<F>
+where
+    F: Filter<u64> + From<Vec<u64>>,
+{
+    /// Construct the filter from a slice of keys split into `num_shards` shards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_shards` is zero.
+    pub fn from_keys(keys: &[u64], num_shards: usize) -> Self {
+        let shards = Self::partition(keys, num_shards);
+        Self::from_shards(shards.into_iter().map(F::from).collect())
+    }
+}
+
+impl<F> ShardedFilter<F>
+where
+    F: Filter<u64> + TryFrom<Vec<u64>>,
+{
+    /// Try to construct the filter from a slice of keys split into `num_shards` shards, using
+    /// shards whose construction may fail, like a [`BinaryFuse8`]. Fails if any shard fails to be
+    /// constructed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_shards` is zero.
+    ///
+    /// [`BinaryFuse8`]: crate::BinaryFuse8
+    pub fn try_from_keys(keys: &[u64], num_shards: usize) -> Result<Self, F::Error> {
+        let shards = Self::partition(keys, num_shards);
+        let shards: Result<Vec<F>, F::Error> = shards.into_iter().map(F::try_from).collect();
+        shards.map(Self::from_shards)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Filter, ShardedFilter, Xor16, Xor8};
+
+    use alloc::vec::Vec;
+    use rand::Rng;
+
+    #[test]
+    fn test_initialization() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter: ShardedFilter<Xor8> = ShardedFilter::from_keys(&keys, 10);
+        assert_eq!(filter.shards().len(), 10);
+        assert_eq!(
+            filter.len(),
+            filter.shards().iter().map(Filter::len).sum::<usize>()
+        );
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_partition() {
+        let keys = [0, u64::MAX / 4, u64::MAX / 2, u64::MAX];
+        let shards = ShardedFilter::<Xor8>::partition(&keys, 2);
+        assert_eq!(
+            shards,
+            [vec![0, u64::MAX / 4, u64::MAX / 2], vec![u64::MAX]]
+        );
+
+        for (i, shard) in ShardedFilter::<Xor8>::partition(&keys, 3)
+            .iter()
+            .enumerate()
+        {
+            for &key in shard {
+                assert_eq!(ShardedFilter::<Xor8>::shard_index(key, 3), i);
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_from_keys() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = ShardedFilter::<Xor16>::try_from_keys(&keys, 7).unwrap();
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let filter = ShardedFilter::<Xor8>::default();
+        assert!(filter.is_empty());
+        assert!(!filter.contains(&0));
+
+        let filter: ShardedFilter<Xor8> = ShardedFilter::from_keys(&[], 4);
+        assert!(filter.is_empty());
+        assert!(!filter.contains(&0));
+    }
+
+    #[test]
+    #[should_panic(expected = "A sharded filter needs at least one shard.")]
+    fn test_no_shards() {
+        let _: ShardedFilter<Xor8> = ShardedFilter::from_keys(&[1, 2, 3], 0);
+    }
+}
@@ -0,0 +1,135 @@
+//! Implements xor filters whose fingerprints are shared between clones.
+
+use crate::{prelude::FingerprintsPreview, xor_contains_impl, Filter, Fingerprint, Xor};
+use alloc::sync::Arc;
+use core::fmt;
+
+/// An [`Xor`] filter whose fingerprints are reference counted, so that cloning it takes constant
+/// time and memory.
+///
+/// A `SharedXor` looks keys up the same way as the `Xor` it is converted from. Clones share their
+/// fingerprints, which makes it cheap to hand a snapshot of a large filter to other threads while
+/// a replacement is built.
+///
+/// ```
+/// # extern crate alloc;
+/// use xorf::{Filter, SharedXor, Xor32};
+/// # use alloc::vec::Vec;
+///
+/// let keys: Vec<u64> = (0..10_000).collect();
+/// let filter = SharedXor::from(Xor32::from(&keys));
+///
+/// let snapshot = filter.clone();
+/// assert!(keys.iter().all(|key| snapshot.contains(key)));
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct SharedXor<F> {
+    /// The seed for the filter
+    pub seed: u64,
+    /// The number of blocks in the filter
+    pub block_length: usize,
+    // Carried over from the `Xor` the filter is converted from.
+    pub(crate) key_count: Option<usize>,
+    /// The fingerprints for the filter, shared between clones
+    pub fingerprints: Arc<[F]>,
+}
+
+impl<F: Fingerprint> Filter<u64> for SharedXor<F> {
+    /// Returns `true` if the filter contains the specified key.
+    /// Has a false positive rate of around `2^-F::BITS`.
+    #[inline]
+    fn contains(&self, key: &u64) -> bool {
+        xor_contains_impl!(*key, self, fingerprint F)
+    }
+
+    fn len(&self) -> usize {
+        self.fingerprints.len()
+    }
+}
+
+impl<F: Fingerprint> SharedXor<F> {
+    /// Returns an iterator over the fingerprint slots of the filter, yielding the index of each
+    /// slot with its fingerprint widened to a `u64`, like [`Xor::slots`].
+    pub fn slots(&self) -> impl Iterator<Item = (usize, u64)> + '_ {
+        self.fingerprints.iter().map(|&f| f.into()).enumerate()
+    }
+}
+
+impl<F: fmt::Debug> fmt::Debug for SharedXor<F> {
+    /// Formats the filter parameters, but only the first few fingerprints.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedXor")
+            .field("seed", &self.seed)
+            .field("block_length", &self.block_length)
+            .field("fingerprints", &FingerprintsPreview(&self.fingerprints))
+            .finish()
+    }
+}
+
+impl<F> From<Xor<F>> for SharedXor<F> {
+    /// Moves the fingerprints of `filter` into shared storage, copying them once.
+    fn from(filter: Xor<F>) -> Self {
+        Self {
+            seed: filter.seed,
+            block_length: filter.block_length,
+            key_count: filter.key_count,
+            fingerprints: filter.fingerprints.into(),
+        }
+    }
+}
+
+impl<F: Clone> From<&SharedXor<F>> for Xor<F> {
+    /// Copies the fingerprints of `filter` into a filter of its own, such as to serialize it.
+    fn from(filter: &SharedXor<F>) -> Self {
+        Self {
+            seed: filter.seed,
+            block_length: filter.block_length,
+            key_count: filter.key_count,
+            fingerprints: filter.fingerprints.iter().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Filter, SharedXor, Xor32};
+
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use rand::Rng;
+
+    #[test]
+    fn test_initialization() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let xor = Xor32::from(&keys);
+        let filter = SharedXor::from(xor.clone());
+        for key in keys.iter() {
+            assert!(filter.contains(key));
+        }
+        for key in (0..SAMPLE_SIZE).map(|_| rng.gen()) {
+            assert_eq!(filter.contains(&key), xor.contains(&key));
+        }
+        assert_eq!(Xor32::from(&filter), xor);
+        assert!(filter.slots().eq(xor.slots()));
+    }
+
+    #[test]
+    fn test_clone_shares_fingerprints() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter = SharedXor::from(Xor32::from(&keys));
+
+        let snapshot = filter.clone();
+        assert!(Arc::ptr_eq(&filter.fingerprints, &snapshot.fingerprints));
+        assert_eq!(Arc::strong_count(&filter.fingerprints), 2);
+    }
+
+    #[test]
+    fn test_empty() {
+        let filter = SharedXor::<u32>::default();
+        assert!(filter.is_empty());
+        assert!(!filter.contains(&0));
+    }
+}
@@ -0,0 +1,518 @@
+//! Implements Xor filters as described in [Xor Filters: Faster and Smaller Than Bloom and Cuckoo Filters].
+//!
+//! [Xor Filters: Faster and Smaller Than Bloom and Cuckoo Filters]: https://arxiv.org/abs/1912.08258
+
+use crate::{
+    prelude::{
+        bytes::{header, key_count_bytes, read_key_count, write_fingerprints, Reader, XOR},
+        fmt_summary, FingerprintsPreview, HashSet,
+    },
+    xor_contains_impl, xor_from_impl, BuildError, Filter, Fingerprint, Probe,
+};
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    convert::{TryFrom, TryInto},
+    fmt,
+    iter::FromIterator,
+};
+
+#[cfg(feature = "std")]
+use crate::prelude::bytes::{invalid_data, write_fingerprints_to, StreamReader};
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bincode")]
+use bincode::{Decode, Encode};
+
+/// Xor filter using fingerprints of type `F`.
+///
+/// An `Xor` filter uses around `1.23 * F::BITS` bits per entry of the set is it constructed from,
+/// and has a false positive rate of around `2^-F::BITS`. Prefer using one of the [`Xor8`],
+/// [`Xor16`], or [`Xor32`] aliases.
+///
+/// An `Xor` is constructed from a set of 64-bit unsigned integers and is immutable.
+///
+/// The default `Xor` is empty, the same as a filter constructed from no keys.
+///
+/// Serializing and deserializing `Xor` filters can be enabled with the [`serde`] feature (or [`bincode`] for bincode).
+///
+/// [`Xor8`]: crate::Xor8
+/// [`Xor16`]: crate::Xor16
+/// [`Xor32`]: crate::Xor32
+/// [`serde`]: http://serde.rs
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Xor<F> {
+    /// The seed for the filter
+    pub seed: u64,
+    /// The number of blocks in the filter
+    pub block_length: usize,
+    // Only recorded by `to_bytes`, so that the serde and bincode layouts stay the same as those
+    // of earlier versions. Filters deserialized from those layouts, or from bytes written before
+    // this was recorded, don't know how many keys they were constructed from.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) key_count: Option<usize>,
+    /// The fingerprints for the filter
+    pub fingerprints: Box<[F]>,
+}
+
+// Encoded by hand, rather than derived, to leave out the key count only recorded by `to_bytes`.
+#[cfg(feature = "bincode")]
+impl<F: Encode> Encode for Xor<F> {
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        self.seed.encode(encoder)?;
+        self.block_length.encode(encoder)?;
+        self.fingerprints.encode(encoder)
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<F: Decode<Context> + 'static, Context> Decode<Context> for Xor<F> {
+    fn decode<D: bincode::de::Decoder<Context = Context>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self {
+            seed: Decode::decode(decoder)?,
+            block_length: Decode::decode(decoder)?,
+            key_count: None,
+            fingerprints: Decode::decode(decoder)?,
+        })
+    }
+}
+
+#[cfg(feature = "bincode")]
+impl<'de, F: bincode::BorrowDecode<'de, Context> + 'de, Context> bincode::BorrowDecode<'de, Context>
+    for Xor<F>
+{
+    fn borrow_decode<D: bincode::de::BorrowDecoder<'de, Context = Context>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self {
+            seed: Decode::decode(decoder)?,
+            block_length: Decode::decode(decoder)?,
+            key_count: None,
+            fingerprints: bincode::BorrowDecode::borrow_decode(decoder)?,
+        })
+    }
+}
+
+impl<F: Fingerprint> Filter<u64> for Xor<F> {
+    /// Returns `true` if the filter contains the specified key.
+    /// Has a false positive rate of around `2^-F::BITS`.
+    #[inline]
+    fn contains(&self, key: &u64) -> bool {
+        xor_contains_impl!(*key, self, fingerprint F)
+    }
+
+    fn len(&self) -> usize {
+        self.fingerprints.len()
+    }
+}
+
+impl<F: Fingerprint> Xor<F> {
+    /// Construct the filter from a key iterator. Can be used directly
+    /// if you don't have a contiguous array of u64 keys.
+    ///
+    /// Note: the iterator will be iterated over multiple times while building
+    /// the filter. If using a hash function to map the key, it may be cheaper
+    /// just to create a scratch array of hashed keys that you pass in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the filter fails to be constructed, which in practice only happens if the keys
+    /// contain duplicates. Use [`try_from_iterator`] to handle this case.
+    ///
+    /// [`try_from_iterator`]: Self::try_from_iterator
+    pub fn from_iterator<T>(keys: T) -> Self
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        Self::try_from_iterator(keys).expect(
+            "Xor filters must be constructed from a collection containing all distinct keys.",
+        )
+    }
+
+    /// Try to construct the filter from a key iterator. Unlike [`from_iterator`], gives up and
+    /// returns an error after a bounded number of construction attempts, which happens if the
+    /// keys contain duplicates. Returns [`BuildError::TooManyKeys`] if there are more keys than a
+    /// filter can index.
+    ///
+    /// Note: the iterator will be iterated over multiple times while building
+    /// the filter. If using a hash function to map the key, it may be cheaper
+    /// just to create a scratch array of hashed keys that you pass in.
+    ///
+    /// [`from_iterator`]: Self::from_iterator
+    pub fn try_from_iterator<T>(keys: T) -> Result<Self, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        xor_from_impl!(keys fingerprint F, max iter 1_000)
+    }
+
+    /// Construct the filter from a key iterator, starting the search for a filter seed from
+    /// `seed`. Filters constructed from the same keys and `seed` are identical, which is useful
+    /// for reproducing a filter.
+    ///
+    /// The seed of the constructed filter is derived from, but generally not equal to, `seed`.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`from_iterator`].
+    ///
+    /// [`from_iterator`]: Self::from_iterator
+    pub fn from_iterator_with_seed<T>(keys: T, seed: u64) -> Self
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        Self::try_from_iterator_with_seed(keys, seed).expect(
+            "Xor filters must be constructed from a collection containing all distinct keys.",
+        )
+    }
+
+    /// Try to construct the filter from a key iterator, starting the search for a filter seed from
+    /// `seed`. See [`from_iterator_with_seed`] and [`try_from_iterator`].
+    ///
+    /// [`from_iterator_with_seed`]: Self::from_iterator_with_seed
+    /// [`try_from_iterator`]: Self::try_from_iterator
+    pub fn try_from_iterator_with_seed<T>(keys: T, seed: u64) -> Result<Self, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        xor_from_impl!(keys fingerprint F, max iter 1_000, rng seed seed)
+    }
+
+    /// Try to construct the filter from a key iterator as [`try_from_iterator`] does, giving up
+    /// after `max_iterations` construction attempts rather than the default of 1,000.
+    ///
+    /// Each attempt tries a new filter seed and takes time linear in the number of keys. Distinct
+    /// keys are almost always placed within a few attempts, so a low cap bounds the time spent on
+    /// keys that contain duplicates.
+    ///
+    /// ```
+    /// # extern crate alloc;
+    /// use xorf::{BuildError, Xor16};
+    /// # use alloc::vec::Vec;
+    ///
+    /// let keys: Vec<u64> = (0..10_000).collect();
+    /// assert!(Xor16::try_from_iterator_with_max_iterations(keys.iter().copied(), 10).is_ok());
+    /// assert_eq!(
+    ///     Xor16::try_from_iterator_with_max_iterations(keys.iter().copied(), 0),
+    ///     Err(BuildError::ConstructionFailed)
+    /// );
+    /// ```
+    ///
+    /// [`try_from_iterator`]: Self::try_from_iterator
+    pub fn try_from_iterator_with_max_iterations<T>(
+        keys: T,
+        max_iterations: usize,
+    ) -> Result<Self, BuildError>
+    where
+        T: ExactSizeIterator<Item = u64> + Clone,
+    {
+        xor_from_impl!(keys fingerprint F, max iter max_iterations)
+    }
+
+    /// Returns the theoretical false positive rate of the filter, `2^-F::BITS`, averaged over all
+    /// keys. See [unused fingerprint slots] for how it varies between keys.
+    ///
+    /// [unused fingerprint slots]: crate#unused-fingerprint-slots
+    pub const fn false_positive_rate(&self) -> f64 {
+        1.0 / (1u128 << F::BITS) as f64
+    }
+
+    /// Returns the number of bits per entry used by the filter if it was constructed from
+    /// `num_keys` keys. All fingerprint slots are counted, including unused ones.
+    pub fn bits_per_entry_over(&self, num_keys: usize) -> f64 {
+        (self.fingerprints.len() as f64) * F::BITS as f64 / (num_keys as f64)
+    }
+
+    /// Estimates the number of keys the filter was constructed from, using only its size.
+    ///
+    /// Construction sizes the filter as `1.23` slots per key plus a constant 32 slots; this
+    /// inverts that relationship, so estimates are within a few keys of the number of keys.
+    pub fn estimated_capacity(&self) -> usize {
+        // Rounding down to a whole number of blocks drops one slot on average.
+        let slots = self.fingerprints.len().saturating_sub(31);
+        (slots as f64 / 1.23 + 0.5) as usize
+    }
+
+    /// Returns the number of keys the filter was constructed from.
+    ///
+    /// The count is only recorded by [`to_bytes`]. Filters deserialized from formats that don't
+    /// record it, such as serde, bincode, the bytes written by [`to_c_layout`], or bytes written by
+    /// earlier versions of this crate, fall back to the [`estimated_capacity`].
+    ///
+    /// [`to_bytes`]: Self::to_bytes
+    /// [`to_c_layout`]: Self::to_c_layout
+    /// [`estimated_capacity`]: Self::estimated_capacity
+    pub fn key_count(&self) -> usize {
+        self.key_count.unwrap_or_else(|| self.estimated_capacity())
+    }
+
+    /// Returns the number of bytes used by the filter, including its fingerprints.
+    pub fn allocated_bytes(&self) -> usize {
+        core::mem::size_of::<Self>() + core::mem::size_of_val(&*self.fingerprints)
+    }
+
+    /// Returns `true` if the filter contains the key whose hash is `hash`, which must be
+    /// `mix(key, self.seed)`, computed with [`mix`]. Agrees with [`contains`] on the key.
+    ///
+    /// Filters with the same seed hash keys the same way, so the hash of a key looked up in
+    /// several of them can be computed once. Seeded constructors only start the search for a seed
+    /// from the one they are given, so compare the seeds of constructed filters rather than
+    /// assuming they are shared.
+    ///
+    /// [`mix`]: crate::hashing::mix
+    /// [`contains`]: Filter::contains
+    #[inline]
+    pub fn contains_mixed(&self, hash: u64) -> bool {
+        xor_contains_impl!(mixed hash, self, fingerprint F)
+    }
+
+    /// Looks up `key` as [`contains`] does, also returning the key's fingerprint and the xor of
+    /// the fingerprints stored in the slots it hashes to. See [`Probe`].
+    ///
+    /// [`contains`]: Filter::contains
+    pub fn probe(&self, key: &u64) -> Probe {
+        let HashSet {
+            hash,
+            hset: [h0, h1, h2],
+        } = HashSet::xor_from(*key, self.block_length, self.seed);
+        let fingerprint = F::from_hash(hash);
+        if self.fingerprints.is_empty() || self.block_length == 0 {
+            return Probe::new(fingerprint, None);
+        }
+        let stored = self.fingerprints[h0]
+            ^ self.fingerprints[h1 + self.block_length]
+            ^ self.fingerprints[h2 + 2 * self.block_length];
+        Probe::new(fingerprint, Some(stored))
+    }
+
+    /// Returns an iterator over the fingerprints of the filter, each widened to a `u64`. Unlike
+    /// the `fingerprints` field, the item type is the same for every filter.
+    pub fn fingerprints(&self) -> impl Iterator<Item = u64> + '_ {
+        self.fingerprints.iter().map(|&f| f.into())
+    }
+
+    /// Returns an iterator over the fingerprint slots of the filter, yielding the index of each
+    /// slot with its fingerprint widened to a `u64`, for tools that inspect the layout of
+    /// fingerprints across filter types.
+    pub fn slots(&self) -> impl Iterator<Item = (usize, u64)> + '_ {
+        self.fingerprints().enumerate()
+    }
+
+    /// Serializes the filter to bytes that can be read back with [`from_bytes`], without
+    /// depending on `serde` or `bincode`. The bytes start with a versioned header recording the
+    /// filter type, followed by the filter parameters and fingerprints as little-endian integers.
+    ///
+    /// The byte order does not depend on the platform, so filters serialized on a little-endian
+    /// target can be read on a big-endian one and vice versa.
+    ///
+    /// [`from_bytes`]: Self::from_bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = header::<F>(XOR);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.block_length as u64).to_le_bytes());
+        bytes.extend_from_slice(&key_count_bytes(self.key_count));
+        write_fingerprints(&mut bytes, &self.fingerprints);
+        bytes
+    }
+
+    /// Deserializes a filter serialized with [`to_bytes`]. Returns an error if `bytes` is not a
+    /// serialized filter of the same type.
+    ///
+    /// [`to_bytes`]: Self::to_bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+        let mut reader = Reader::new::<F>(bytes, XOR)?;
+        let seed = reader.u64()?;
+        let block_length = reader.u64()?;
+        let key_count = if reader.version >= 2 {
+            read_key_count(reader.u64()?)
+                .map_err(|_| "Filter bytes have invalid filter parameters.")?
+        } else {
+            None
+        };
+        let filter = Self {
+            seed,
+            block_length: usize::try_from(block_length)
+                .map_err(|_| "Filter bytes have invalid filter parameters.")?,
+            key_count,
+            fingerprints: reader.fingerprints()?,
+        };
+        filter
+            .validate()
+            .map_err(|_| "Filter bytes have invalid filter parameters.")?;
+        Ok(filter)
+    }
+
+    /// Writes the filter to `w` in the format of [`to_bytes`], without collecting the bytes in
+    /// memory first. Fingerprints are converted and written a chunk at a time, so this is suitable
+    /// for filters too large to copy.
+    ///
+    /// [`to_bytes`]: Self::to_bytes
+    #[cfg(feature = "std")]
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&header::<F>(XOR))?;
+        w.write_all(&self.seed.to_le_bytes())?;
+        w.write_all(&(self.block_length as u64).to_le_bytes())?;
+        w.write_all(&key_count_bytes(self.key_count))?;
+        write_fingerprints_to(w, &self.fingerprints)
+    }
+
+    /// Reads a filter written with [`write_to`] or [`to_bytes`] from `r`, leaving anything after
+    /// the filter unread. Returns an error of kind [`InvalidData`] if the bytes are not a
+    /// serialized filter of the same type.
+    ///
+    /// [`write_to`]: Self::write_to
+    /// [`to_bytes`]: Self::to_bytes
+    /// [`InvalidData`]: io::ErrorKind::InvalidData
+    #[cfg(feature = "std")]
+    pub fn read_from<R: Read>(r: &mut R) -> io::Result<Self> {
+        let mut reader = StreamReader::new::<F>(r, XOR)?;
+        let seed = reader.u64()?;
+        let block_length = reader.u64()?;
+        let key_count = if reader.version >= 2 {
+            read_key_count(reader.u64()?)
+                .map_err(|_| invalid_data("Filter bytes have invalid filter parameters."))?
+        } else {
+            None
+        };
+        let filter = Self {
+            seed,
+            block_length: usize::try_from(block_length)
+                .map_err(|_| invalid_data("Filter bytes have invalid filter parameters."))?,
+            key_count,
+            fingerprints: reader.fingerprints()?,
+        };
+        filter
+            .validate()
+            .map_err(|_| invalid_data("Filter bytes have invalid filter parameters."))?;
+        Ok(filter)
+    }
+
+    /// Checks that the block length of the filter is consistent with its number of fingerprints.
+    /// Filters deserialized with `serde` or `bincode` from untrusted data should be validated
+    /// before use, since [`contains`] on an inconsistent filter may panic.
+    ///
+    /// Filters constructed from keys or read with [`from_bytes`] are always valid.
+    ///
+    /// [`contains`]: Filter::contains
+    /// [`from_bytes`]: Self::from_bytes
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.block_length.checked_mul(3) == Some(self.fingerprints.len()) {
+            Ok(())
+        } else {
+            Err("Filter parameters are inconsistent with its fingerprints.")
+        }
+    }
+
+    /// Serializes the filter in the layout used by `xor8_serialize` and `xor16_serialize` in
+    /// FastFilter's [`xor_singleheader`] C library: the seed and block length as 64-bit integers,
+    /// followed by the fingerprints. Filters are hashed the same way in both libraries, so a
+    /// filter serialized here can be queried from C and vice versa.
+    ///
+    /// All integers are written in little-endian order, which is what the C library writes on
+    /// little-endian targets.
+    ///
+    /// [`xor_singleheader`]: https://github.com/FastFilter/xor_singleheader
+    pub fn to_c_layout(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16 + core::mem::size_of_val(&*self.fingerprints));
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.block_length as u64).to_le_bytes());
+        for &fingerprint in self.fingerprints.iter() {
+            fingerprint.write_le_bytes(&mut bytes);
+        }
+        bytes
+    }
+
+    /// Deserializes a filter in the layout written by [`to_c_layout`]. Returns an error if the
+    /// length of `bytes` does not match the block length it records.
+    ///
+    /// [`to_c_layout`]: Self::to_c_layout
+    pub fn from_c_layout(bytes: &[u8]) -> Result<Self, &'static str> {
+        if bytes.len() < 16 {
+            return Err("Filter bytes are truncated.");
+        }
+        let (header, fingerprints) = bytes.split_at(16);
+        let seed = u64::from_le_bytes(header[..8].try_into().unwrap());
+        let block_length = u64::from_le_bytes(header[8..].try_into().unwrap());
+        let width = (F::BITS / 8) as u64;
+        if block_length.checked_mul(3 * width) != Some(fingerprints.len() as u64) {
+            return Err("Filter bytes have invalid filter parameters.");
+        }
+        Ok(Self {
+            seed,
+            block_length: block_length as usize,
+            key_count: None,
+            fingerprints: fingerprints
+                .chunks_exact(width as usize)
+                .map(F::read_le_bytes)
+                .collect(),
+        })
+    }
+}
+
+impl<F: fmt::Debug> fmt::Debug for Xor<F> {
+    /// Formats the filter parameters, but only the first few fingerprints.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Xor")
+            .field("seed", &self.seed)
+            .field("block_length", &self.block_length)
+            .field("fingerprints", &FingerprintsPreview(&self.fingerprints))
+            .finish()
+    }
+}
+
+impl<F: Fingerprint> fmt::Display for Xor<F> {
+    /// Summarizes the filter on one line, with bits per entry computed over the [`key_count`].
+    ///
+    /// [`key_count`]: Self::key_count
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_summary(f, "Xor", F::BITS, self.fingerprints.len(), self.key_count())
+    }
+}
+
+impl<F: Fingerprint> From<&[u64]> for Xor<F> {
+    fn from(keys: &[u64]) -> Self {
+        Self::from_iterator(keys.iter().copied())
+    }
+}
+
+impl<F: Fingerprint, const N: usize> From<&[u64; N]> for Xor<F> {
+    fn from(keys: &[u64; N]) -> Self {
+        Self::from(&keys[..])
+    }
+}
+
+impl<F: Fingerprint> From<&Vec<u64>> for Xor<F> {
+    fn from(v: &Vec<u64>) -> Self {
+        Self::from_iterator(v.iter().copied())
+    }
+}
+
+impl<F: Fingerprint> From<Vec<u64>> for Xor<F> {
+    fn from(v: Vec<u64>) -> Self {
+        Self::from_iterator(v.iter().copied())
+    }
+}
+
+impl<F: Fingerprint> FromIterator<u64> for Xor<F> {
+    /// Collects the keys into a `Vec` and constructs the filter from it, since construction
+    /// iterates over the keys multiple times.
+    ///
+    /// # Panics
+    ///
+    /// Panics under the same conditions as [`from_iterator`].
+    ///
+    /// [`from_iterator`]: Self::from_iterator
+    fn from_iter<T: IntoIterator<Item = u64>>(keys: T) -> Self {
+        let keys: Vec<u64> = keys.into_iter().collect();
+        Self::from(keys)
+    }
+}
@@ -2,14 +2,7 @@
 //!
 //! [Xor Filters: Faster and Smaller Than Bloom and Cuckoo Filters]: https://arxiv.org/abs/1912.08258
 
-use crate::{xor_contains_impl, xor_from_impl, Filter};
-use alloc::{boxed::Box, vec::Vec};
-
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
-
-#[cfg(feature = "bincode")]
-use bincode::{Decode, Encode};
+use crate::Xor;
 
 /// Xor filter using 16-bit fingerprints.
 ///
@@ -36,7 +29,7 @@ use bincode::{Decode, Encode};
 /// }
 ///
 /// // bits per entry
-/// let bpe = (filter.len() as f64) * 16.0 / (SAMPLE_SIZE as f64);
+/// let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 /// assert!(bpe < 20., "Bits per entry is {}", bpe);
 ///
 /// // false positive rate
@@ -51,61 +44,7 @@ use bincode::{Decode, Encode};
 /// Serializing and deserializing `Xor16` filters can be enabled with the [`serde`] feature  (or [`bincode`] for bincode).
 ///
 /// [`serde`]: http://serde.rs
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "bincode", derive(Encode, Decode))]
-#[derive(Debug, Clone)]
-pub struct Xor16 {
-    /// The seed for the filter
-    pub seed: u64,
-    /// The number of blocks in the filter
-    pub block_length: usize,
-    /// The fingerprints for the filter
-    pub fingerprints: Box<[u16]>,
-}
-
-impl Filter<u64> for Xor16 {
-    /// Returns `true` if the filter contains the specified key. Has a false positive rate of <0.002%.
-    fn contains(&self, key: &u64) -> bool {
-        xor_contains_impl!(*key, self, fingerprint u16)
-    }
-
-    fn len(&self) -> usize {
-        self.fingerprints.len()
-    }
-}
-
-impl Xor16 {
-    /// Construct the filter from a key iterator. Can be used directly
-    /// if you don't have a contiguous array of u64 keys.
-    ///
-    /// Note: the iterator will be iterated over multiple times while building
-    /// the filter. If using a hash function to map the key, it may be cheaper
-    /// just to create a scratch array of hashed keys that you pass in.
-    pub fn from_iterator<T>(keys: T) -> Self
-    where
-        T: ExactSizeIterator<Item = u64> + Clone,
-    {
-        xor_from_impl!(keys fingerprint u16)
-    }
-}
-
-impl From<&[u64]> for Xor16 {
-    fn from(keys: &[u64]) -> Self {
-        Self::from_iterator(keys.iter().copied())
-    }
-}
-
-impl From<&Vec<u64>> for Xor16 {
-    fn from(v: &Vec<u64>) -> Self {
-        Self::from_iterator(v.iter().copied())
-    }
-}
-
-impl From<Vec<u64>> for Xor16 {
-    fn from(v: Vec<u64>) -> Self {
-        Self::from_iterator(v.iter().copied())
-    }
-}
+pub type Xor16 = Xor<u16>;
 
 #[cfg(test)]
 mod test {
@@ -134,7 +73,7 @@ mod test {
         let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
 
         let filter = Xor16::from(&keys);
-        let bpe = (filter.len() as f64) * 16.0 / (SAMPLE_SIZE as f64);
+        let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 
         assert!(bpe < 20., "Bits per entry is {}", bpe);
     }
@@ -154,4 +93,133 @@ mod test {
         let fp_rate: f64 = (false_positives * 100) as f64 / SAMPLE_SIZE as f64;
         assert!(fp_rate < 0.0025, "False positive rate is {}", fp_rate);
     }
+
+    #[test]
+    fn test_construction_with_seed() {
+        let keys: Vec<u64> = (0..1_000).collect();
+
+        let a = Xor16::from_iterator_with_seed(keys.iter().copied(), 42);
+        let b = Xor16::from_iterator_with_seed(keys.iter().copied(), 42);
+        assert_eq!(a.seed, b.seed);
+        assert_eq!(a.fingerprints, b.fingerprints);
+        assert_eq!(a, b);
+
+        let c = Xor16::from_iterator_with_seed(keys.iter().copied(), 43);
+        assert_ne!(a.seed, c.seed);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_construction_with_max_iterations() {
+        let keys: Vec<u64> = (0..10_000).collect();
+
+        let filter = Xor16::try_from_iterator_with_max_iterations(keys.iter().copied(), 1_000);
+        assert_eq!(filter, Xor16::try_from_iterator(keys.iter().copied()));
+        assert_eq!(
+            Xor16::try_from_iterator_with_max_iterations(keys.iter().copied(), 0),
+            Err(crate::BuildError::ConstructionFailed)
+        );
+    }
+
+    #[test]
+    fn test_contains_mixed() {
+        use crate::hashing::mix;
+
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+        let filter = Xor16::from(&keys);
+
+        for key in keys.iter() {
+            assert!(filter.contains_mixed(mix(*key, filter.seed)));
+        }
+        for key in (0..SAMPLE_SIZE).map(|_| rng.gen()) {
+            assert_eq!(
+                filter.contains_mixed(mix(key, filter.seed)),
+                filter.contains(&key)
+            );
+        }
+        assert!(!Xor16::default().contains_mixed(0));
+    }
+
+    #[test]
+    fn test_collect() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter: Xor16 = keys.iter().copied().collect();
+
+        assert_eq!(filter, Xor16::from(&keys));
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_default() {
+        let filter = Xor16::default();
+        assert_eq!(filter.len(), 0);
+        assert!((0..1_000).all(|key| !filter.contains(&key)));
+    }
+
+    #[test]
+    fn test_allocated_bytes() {
+        let keys: Vec<u64> = (0..1_000).collect();
+        let filter = Xor16::from(&keys);
+
+        let fingerprint_bytes = filter.len() * core::mem::size_of::<u16>();
+        let overhead = filter.allocated_bytes() - fingerprint_bytes;
+        assert!(overhead <= 64, "Overhead is {} bytes", overhead);
+    }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = Xor16::from(&keys);
+        let bytes = filter.to_bytes();
+        let deserialized = Xor16::from_bytes(&bytes).unwrap();
+
+        assert_eq!(filter.seed, deserialized.seed);
+        assert_eq!(filter.block_length, deserialized.block_length);
+        assert_eq!(filter.fingerprints, deserialized.fingerprints);
+
+        assert!(Xor16::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(crate::Xor8::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_bytes_layout() {
+        let keys: Vec<u64> = (0..1_000).collect();
+        let filter = Xor16::from(&keys);
+        let bytes = filter.to_bytes();
+
+        assert_eq!(&bytes[..7], b"XORF\x02\x00\x10");
+        assert_eq!(bytes[7..15], filter.seed.to_le_bytes());
+        assert_eq!(bytes[15..23], (filter.block_length as u64).to_le_bytes());
+        assert_eq!(bytes[23..31], 1_000u64.to_le_bytes());
+        assert_eq!(bytes[31..39], (filter.len() as u64).to_le_bytes());
+        for (fingerprint, chunk) in filter.fingerprints.iter().zip(bytes[39..].chunks(2)) {
+            assert_eq!(chunk, fingerprint.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn test_from_version_1_bytes() {
+        let keys: Vec<u64> = (0..1_000).collect();
+        let filter = Xor16::from(&keys);
+
+        // Version 1 bytes have no key count after the parameters.
+        let mut bytes = filter.to_bytes();
+        bytes[4] = 1;
+        bytes.drain(23..31);
+
+        let deserialized = Xor16::from_bytes(&bytes).unwrap();
+        assert_eq!(deserialized.key_count, None);
+        assert_eq!(deserialized.key_count(), filter.estimated_capacity());
+        assert_eq!(filter.fingerprints, deserialized.fingerprints);
+
+        bytes[4] = 3;
+        assert!(Xor16::from_bytes(&bytes).is_err());
+    }
 }
@@ -2,14 +2,7 @@
 //!
 //! [Xor Filters: Faster and Smaller Than Bloom and Cuckoo Filters]: https://arxiv.org/abs/1912.08258
 
-use crate::{xor_contains_impl, xor_from_impl, Filter};
-use alloc::{boxed::Box, vec::Vec};
-
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
-
-#[cfg(feature = "bincode")]
-use bincode::{Decode, Encode};
+use crate::Xor;
 
 /// Xor filter using 32-bit fingerprints.
 ///
@@ -36,7 +29,7 @@ use bincode::{Decode, Encode};
 /// }
 ///
 /// // bits per entry
-/// let bpe = (filter.len() as f64) * 32.0 / (SAMPLE_SIZE as f64);
+/// let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 /// assert!(bpe < 40., "Bits per entry is {}", bpe);
 ///
 /// // false positive rate
@@ -51,61 +44,7 @@ use bincode::{Decode, Encode};
 /// Serializing and deserializing `Xor32` filters can be enabled with the [`serde`] feature  (or [`bincode`] for bincode).
 ///
 /// [`serde`]: http://serde.rs
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "bincode", derive(Encode, Decode))]
-#[derive(Debug, Clone)]
-pub struct Xor32 {
-    /// The seed for the filter
-    pub seed: u64,
-    /// The number of blocks in the filter
-    pub block_length: usize,
-    /// The fingerprints for the filter
-    pub fingerprints: Box<[u32]>,
-}
-
-impl Filter<u64> for Xor32 {
-    /// Returns `true` if the filter contains the specified key.
-    fn contains(&self, key: &u64) -> bool {
-        xor_contains_impl!(*key, self, fingerprint u32)
-    }
-
-    fn len(&self) -> usize {
-        self.fingerprints.len()
-    }
-}
-
-impl Xor32 {
-    /// Construct the filter from a key iterator. Can be used directly
-    /// if you don't have a contiguous array of u64 keys.
-    ///
-    /// Note: the iterator will be iterated over multiple times while building
-    /// the filter. If using a hash function to map the key, it may be cheaper
-    /// just to create a scratch array of hashed keys that you pass in.
-    pub fn from_iterator<T>(keys: T) -> Self
-    where
-        T: ExactSizeIterator<Item = u64> + Clone,
-    {
-        xor_from_impl!(keys fingerprint u32)
-    }
-}
-
-impl From<&[u64]> for Xor32 {
-    fn from(keys: &[u64]) -> Self {
-        Self::from_iterator(keys.iter().copied())
-    }
-}
-
-impl From<&Vec<u64>> for Xor32 {
-    fn from(v: &Vec<u64>) -> Self {
-        Self::from_iterator(v.iter().copied())
-    }
-}
-
-impl From<Vec<u64>> for Xor32 {
-    fn from(v: Vec<u64>) -> Self {
-        Self::from_iterator(v.iter().copied())
-    }
-}
+pub type Xor32 = Xor<u32>;
 
 #[cfg(test)]
 mod test {
@@ -134,7 +73,7 @@ mod test {
         let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
 
         let filter = Xor32::from(&keys);
-        let bpe = (filter.len() as f64) * 32.0 / (SAMPLE_SIZE as f64);
+        let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 
         assert!(bpe < 40., "Bits per entry is {}", bpe);
     }
@@ -166,4 +105,29 @@ mod test {
             fp_rate
         );
     }
+
+    #[test]
+    fn test_validate() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let mut filter = Xor32::from(&keys);
+        assert_eq!(filter.validate(), Ok(()));
+
+        filter.block_length += 1;
+        assert_eq!(
+            filter.validate(),
+            Err("Filter parameters are inconsistent with its fingerprints.")
+        );
+    }
+
+    #[test]
+    fn test_fingerprints() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter = Xor32::from(&keys);
+
+        assert_eq!(filter.fingerprints().count(), filter.len());
+        assert!(filter
+            .fingerprints()
+            .zip(filter.fingerprints.iter())
+            .all(|(wide, &f)| wide == f as u64));
+    }
 }
@@ -0,0 +1,88 @@
+//! Implements Xor64 filters as described in [Xor Filters: Faster and Smaller Than Bloom and Cuckoo Filters].
+//!
+//! [Xor Filters: Faster and Smaller Than Bloom and Cuckoo Filters]: https://arxiv.org/abs/1912.08258
+
+use crate::Xor;
+
+/// Xor filter using 64-bit fingerprints.
+///
+/// An `Xor64` filter uses <80 bits per entry of the set is it constructed from, and has a false
+/// positive rate of around 1/2^64 =~ 1/18 quintillion, for applications where a false positive
+/// must be practically impossible. The fingerprint of a key is its whole 64-bit hash.
+///
+/// An `Xor64` is constructed from a set of 64-bit unsigned integers and is immutable.
+///
+/// ```
+/// # extern crate alloc;
+/// use xorf::{Filter, Xor64};
+/// # use alloc::vec::Vec;
+/// # use rand::Rng;
+///
+/// # let mut rng = rand::thread_rng();
+/// const SAMPLE_SIZE: usize = 1_000_000;
+/// let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+/// let filter = Xor64::from(&keys);
+///
+/// // no false negatives
+/// for key in keys {
+///     assert!(filter.contains(&key));
+/// }
+///
+/// // bits per entry
+/// let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
+/// assert!(bpe < 80., "Bits per entry is {}", bpe);
+///
+/// // false positive rate
+/// let false_positives: usize = (0..SAMPLE_SIZE)
+///     .map(|_| rng.gen())
+///     .filter(|n| filter.contains(n))
+///     .count();
+/// assert_eq!(false_positives, 0);
+/// ```
+///
+/// Serializing and deserializing `Xor64` filters can be enabled with the [`serde`] feature  (or [`bincode`] for bincode).
+///
+/// [`serde`]: http://serde.rs
+pub type Xor64 = Xor<u64>;
+
+#[cfg(test)]
+mod test {
+    use crate::{Filter, Fingerprint, Xor64};
+
+    use alloc::vec::Vec;
+    use rand::Rng;
+
+    #[test]
+    fn test_initialization() {
+        const SAMPLE_SIZE: usize = 1_000_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = Xor64::from(&keys);
+
+        for key in keys {
+            assert!(filter.contains(&key));
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_is_whole_hash() {
+        let hash = 0x0123_4567_89ab_cdef;
+        assert_eq!(u64::from_hash(hash), hash);
+        assert_eq!(u32::from_hash(hash), 0x89ab_cdef ^ 0x0123_4567);
+    }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter = Xor64::from(&keys);
+
+        let deserialized = Xor64::from_bytes(&filter.to_bytes()).unwrap();
+        assert_eq!(filter, deserialized);
+        // The C layout doesn't record the key count.
+        let deserialized = Xor64::from_c_layout(&filter.to_c_layout()).unwrap();
+        assert_eq!(filter.seed, deserialized.seed);
+        assert_eq!(filter.block_length, deserialized.block_length);
+        assert_eq!(filter.fingerprints, deserialized.fingerprints);
+    }
+}
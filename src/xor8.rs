@@ -2,14 +2,7 @@
 //!
 //! [Xor Filters: Faster and Smaller Than Bloom and Cuckoo Filters]: https://arxiv.org/abs/1912.08258
 
-use crate::{xor_contains_impl, xor_from_impl, Filter};
-use alloc::{boxed::Box, vec::Vec};
-
-#[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
-
-#[cfg(feature = "bincode")]
-use bincode::{Decode, Encode};
+use crate::Xor;
 
 /// Xor filter using 8-bit fingerprints.
 ///
@@ -36,7 +29,7 @@ use bincode::{Decode, Encode};
 /// }
 ///
 /// // bits per entry
-/// let bpe = (filter.len() as f64) * 8.0 / (SAMPLE_SIZE as f64);
+/// let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 /// assert!(bpe < 10., "Bits per entry is {}", bpe);
 ///
 /// // false positive rate
@@ -51,61 +44,7 @@ use bincode::{Decode, Encode};
 /// Serializing and deserializing `Xor8` filters can be enabled with the [`serde`] feature (or [`bincode`] for bincode).
 ///
 /// [`serde`]: http://serde.rs
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "bincode", derive(Encode, Decode))]
-#[derive(Debug, Clone)]
-pub struct Xor8 {
-    /// The seed for the filter
-    pub seed: u64,
-    /// The number of blocks in the filter
-    pub block_length: usize,
-    /// The fingerprints for the filter
-    pub fingerprints: Box<[u8]>,
-}
-
-impl Filter<u64> for Xor8 {
-    /// Returns `true` if the filter contains the specified key. Has a false positive rate of <0.4%.
-    fn contains(&self, key: &u64) -> bool {
-        xor_contains_impl!(*key, self, fingerprint u8)
-    }
-
-    fn len(&self) -> usize {
-        self.fingerprints.len()
-    }
-}
-
-impl Xor8 {
-    /// Construct the filter from a key iterator. Can be used directly
-    /// if you don't have a contiguous array of u64 keys.
-    ///
-    /// Note: the iterator will be iterated over multiple times while building
-    /// the filter. If using a hash function to map the key, it may be cheaper
-    /// just to create a scratch array of hashed keys that you pass in.
-    pub fn from_iterator<T>(keys: T) -> Self
-    where
-        T: ExactSizeIterator<Item = u64> + Clone,
-    {
-        xor_from_impl!(keys fingerprint u8)
-    }
-}
-
-impl From<&[u64]> for Xor8 {
-    fn from(keys: &[u64]) -> Self {
-        Self::from_iterator(keys.iter().copied())
-    }
-}
-
-impl From<&Vec<u64>> for Xor8 {
-    fn from(v: &Vec<u64>) -> Self {
-        Self::from_iterator(v.iter().copied())
-    }
-}
-
-impl From<Vec<u64>> for Xor8 {
-    fn from(v: Vec<u64>) -> Self {
-        Self::from_iterator(v.iter().copied())
-    }
-}
+pub type Xor8 = Xor<u8>;
 
 #[cfg(test)]
 mod test {
@@ -134,7 +73,7 @@ mod test {
         let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
 
         let filter = Xor8::from(&keys);
-        let bpe = (filter.len() as f64) * 8.0 / (SAMPLE_SIZE as f64);
+        let bpe = filter.bits_per_entry_over(SAMPLE_SIZE);
 
         assert!(bpe < 10., "Bits per entry is {}", bpe);
     }
@@ -155,6 +94,143 @@ mod test {
         assert!(fp_rate < 0.406, "False positive rate is {}", fp_rate);
     }
 
+    #[test]
+    fn test_is_empty() {
+        let filter = Xor8::from(vec![1, 2, 3]);
+        assert!(!filter.is_empty());
+    }
+
+    #[test]
+    fn test_estimated_capacity() {
+        for size in [10, 1_000, 100_000] {
+            let keys: Vec<u64> = (0..size).collect();
+            let filter = Xor8::from(&keys);
+            let estimate = filter.estimated_capacity() as i64;
+            assert!(
+                (estimate - size as i64).abs() <= 2,
+                "Estimated {} keys for {}",
+                estimate,
+                size
+            );
+        }
+        assert_eq!(Xor8::default().estimated_capacity(), 0);
+    }
+
+    #[test]
+    fn test_key_count() {
+        let keys: Vec<u64> = (0..10_000).collect();
+        let filter = Xor8::from(&keys);
+        assert_eq!(filter.key_count(), 10_000);
+        assert_eq!(
+            Xor8::from_bytes(&filter.to_bytes()).unwrap().key_count(),
+            10_000
+        );
+
+        let deserialized = Xor8::from_c_layout(&filter.to_c_layout()).unwrap();
+        assert_eq!(deserialized.key_count, None);
+        assert_eq!(deserialized.key_count(), filter.estimated_capacity());
+        assert_eq!(Xor8::default().key_count(), 0);
+    }
+
+    #[test]
+    fn test_block_length() {
+        use crate::prelude::xor::block_length;
+        use crate::BuildError;
+
+        for size in [1, 99, 100, 10_000, 1_234_567] {
+            let expected = ((1.23 * size as f64) as usize + 32) / 3;
+            assert_eq!(block_length(size), Ok(expected));
+        }
+        assert_eq!(
+            Xor8::from(&[1, 2, 3]).block_length,
+            block_length(3).unwrap()
+        );
+        assert_eq!(block_length(usize::MAX), Err(BuildError::TooManyKeys));
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(block_length(12_000_000_000), Err(BuildError::TooManyKeys));
+    }
+
+    #[test]
+    fn test_display() {
+        let keys: Vec<u64> = (0..1_000).collect();
+        let filter = Xor8::from(&keys);
+        assert_eq!(
+            format!("{}", filter),
+            "Xor8 { fingerprints: 1260, estimated keys: 1000, bits/entry: 10.08, fp_rate: ~2^-8 }"
+        );
+        assert_eq!(
+            format!("{}", Xor8::default()),
+            "Xor8 { fingerprints: 0, estimated keys: 0, bits/entry: 0.00, fp_rate: ~2^-8 }"
+        );
+    }
+
+    #[test]
+    fn test_debug() {
+        let filter = Xor8 {
+            seed: 1,
+            block_length: 2,
+            key_count: None,
+            fingerprints: vec![1, 2, 3, 4, 5, 6].into_boxed_slice(),
+        };
+        assert_eq!(
+            format!("{:?}", filter),
+            "Xor { seed: 1, block_length: 2, fingerprints: [1, 2, 3, 4, .. len 6 ..] }"
+        );
+        assert_eq!(
+            format!("{:?}", Xor8::default()),
+            "Xor { seed: 0, block_length: 0, fingerprints: [] }"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_stream_roundtrip() {
+        let keys: Vec<u64> = (0..100_000).collect();
+        let filter = Xor8::from(&keys);
+
+        let mut bytes = Vec::new();
+        filter.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes, filter.to_bytes());
+        assert_eq!(Xor8::read_from(&mut bytes.as_slice()).unwrap(), filter);
+    }
+
+    #[test]
+    fn test_empty_and_single_key() {
+        let filter = Xor8::from(Vec::new());
+        assert!(filter.is_empty());
+        assert!((0..1_000).all(|key| !filter.contains(&key)));
+
+        let deserialized = Xor8::from_bytes(&filter.to_bytes()).unwrap();
+        assert!(deserialized.is_empty());
+        assert!(!deserialized.contains(&0));
+
+        let filter = Xor8 {
+            seed: 0,
+            block_length: 0,
+            key_count: None,
+            fingerprints: vec![0; 3].into_boxed_slice(),
+        };
+        assert!((0..1_000).all(|key| !filter.contains(&key)));
+
+        let key = rand::random();
+        let filter = Xor8::from(vec![key]);
+        assert!(filter.contains(&key));
+    }
+
+    #[test]
+    fn test_from_array() {
+        let filter = Xor8::from(&[1, 2, 3]);
+        assert!([1, 2, 3].iter().all(|key| filter.contains(key)));
+        assert_eq!(filter, Xor8::from(&[1, 2, 3][..]));
+    }
+
+    #[test]
+    #[cfg(not(debug_assertions))]
+    fn test_fail_construction_with_duplicates() {
+        let filter = Xor8::try_from_iterator([1, 2, 1].iter().copied());
+        assert!(filter.expect_err("") == crate::BuildError::ConstructionFailed);
+    }
+
     #[test]
     #[cfg(debug_assertions)]
     #[should_panic(
@@ -163,4 +239,75 @@ mod test {
     fn test_debug_assert_duplicates() {
         let _ = Xor8::from(vec![1, 2, 1]);
     }
+
+    #[test]
+    #[cfg(feature = "bincode")]
+    fn test_bincode_roundtrip() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = Xor8::from(&keys);
+        let config = bincode::config::standard();
+        let bytes = bincode::encode_to_vec(&filter, config).unwrap();
+        let (deserialized, read): (Xor8, usize) =
+            bincode::decode_from_slice(&bytes, config).unwrap();
+
+        assert_eq!(read, bytes.len());
+        assert_eq!(filter.seed, deserialized.seed);
+        assert_eq!(filter.block_length, deserialized.block_length);
+        assert_eq!(filter.fingerprints, deserialized.fingerprints);
+    }
+
+    #[test]
+    #[cfg(feature = "bincode")]
+    fn test_bincode_from_earlier_version() {
+        // An `Xor8` of these keys encoded with `bincode::config::standard()` by a version of this
+        // crate from before filters recorded their key count.
+        const BYTES: [u8; 53] = [
+            253, 193, 92, 2, 137, 236, 45, 10, 145, 14, 42, 233, 0, 221, 170, 0, 0, 159, 0, 0, 0,
+            0, 0, 0, 61, 221, 0, 246, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 0, 0, 139, 0, 0, 0, 0, 0, 0,
+            0, 0, 52, 0, 0,
+        ];
+        let keys: Vec<u64> = (0..10u64)
+            .map(|key| key.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .collect();
+
+        let config = bincode::config::standard();
+        let (filter, read): (Xor8, usize) = bincode::decode_from_slice(&BYTES, config).unwrap();
+        assert_eq!(read, BYTES.len());
+        assert!(keys.iter().all(|key| filter.contains(key)));
+        assert_eq!(filter.key_count, None);
+        assert_eq!(bincode::encode_to_vec(&filter, config).unwrap(), BYTES);
+
+        // The key count is left out of the encoding, so it isn't carried through bincode.
+        let filter = Xor8::from(&keys);
+        let bytes = bincode::encode_to_vec(&filter, config).unwrap();
+        let (deserialized, _): (Xor8, usize) =
+            bincode::borrow_decode_from_slice(&bytes, config).unwrap();
+        assert_eq!(deserialized.key_count, None);
+        assert_eq!(filter.fingerprints, deserialized.fingerprints);
+    }
+
+    #[test]
+    fn test_c_layout_roundtrip() {
+        const SAMPLE_SIZE: usize = 100_000;
+        let mut rng = rand::thread_rng();
+        let keys: Vec<u64> = (0..SAMPLE_SIZE).map(|_| rng.gen()).collect();
+
+        let filter = Xor8::from(&keys);
+        let bytes = filter.to_c_layout();
+        assert_eq!(bytes.len(), 16 + 3 * filter.block_length);
+        assert_eq!(bytes[..8], filter.seed.to_le_bytes());
+        assert_eq!(bytes[8..16], (filter.block_length as u64).to_le_bytes());
+        assert_eq!(bytes[16..], *filter.fingerprints);
+
+        let deserialized = Xor8::from_c_layout(&bytes).unwrap();
+        assert_eq!(filter.seed, deserialized.seed);
+        assert_eq!(filter.block_length, deserialized.block_length);
+        assert_eq!(filter.fingerprints, deserialized.fingerprints);
+
+        assert!(Xor8::from_c_layout(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Xor8::from_c_layout(&bytes[..8]).is_err());
+    }
 }
@@ -0,0 +1,55 @@
+// Writes tests/fixtures/go_binary_fuse8.bin, a BinaryFuse8 filter constructed by the Go xorfilter
+// library, in the layout read by BinaryFuse8::from_go_bytes.
+//
+// From this directory, run
+//
+//	go mod init xorf-fixtures
+//	go get github.com/FastFilter/xorfilter
+//	go run . ../go_binary_fuse8.bin
+//
+// then commit the fixture and remove the #[ignore] from the test in tests/go_fixture.rs.
+package main
+
+import (
+	"bufio"
+	"encoding/binary"
+	"log"
+	"os"
+
+	"github.com/FastFilter/xorfilter"
+)
+
+// Must match the keys in tests/go_fixture.rs.
+const numKeys = 10000
+
+func main() {
+	keys := make([]uint64, numKeys)
+	for i := range keys {
+		keys[i] = uint64(i) * 0x9E3779B97F4A7C15
+	}
+	filter, err := xorfilter.PopulateBinaryFuse8(keys)
+	if err != nil {
+		log.Fatal(err)
+	}
+
+	file, err := os.Create(os.Args[1])
+	if err != nil {
+		log.Fatal(err)
+	}
+	defer file.Close()
+	w := bufio.NewWriter(file)
+	for _, field := range []any{
+		filter.Seed,
+		filter.SegmentLength,
+		filter.SegmentLengthMask,
+		filter.SegmentCountLength,
+		filter.Fingerprints,
+	} {
+		if err := binary.Write(w, binary.LittleEndian, field); err != nil {
+			log.Fatal(err)
+		}
+	}
+	if err := w.Flush(); err != nil {
+		log.Fatal(err)
+	}
+}
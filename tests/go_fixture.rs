@@ -0,0 +1,28 @@
+//! Tests that filters constructed by the Go xorfilter library are read and queried correctly.
+
+#![cfg(feature = "binary-fuse")]
+
+use xorf::{BinaryFuse8, Filter};
+
+// Written by `tests/fixtures/go/main.go`, which documents how to run it.
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/fixtures/go_binary_fuse8.bin"
+);
+
+// Must match the keys in `tests/fixtures/go/main.go`.
+fn keys() -> impl Iterator<Item = u64> {
+    (0..10_000u64).map(|key| key.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+#[test]
+// The fixture has to be generated with the Go toolchain and isn't committed yet. Once it is,
+// remove this attribute so the test always runs.
+#[ignore = "tests/fixtures/go_binary_fuse8.bin is not committed; generate it with tests/fixtures/go/main.go"]
+fn go_binary_fuse8() {
+    let bytes = std::fs::read(FIXTURE).unwrap();
+    let filter = BinaryFuse8::from_go_bytes(&bytes).unwrap();
+
+    assert!(keys().all(|key| filter.contains(&key)));
+    assert!(!filter.empty_slots_randomized());
+}
@@ -0,0 +1,96 @@
+//! Property tests that every filter contains every key it is constructed from.
+
+#![allow(deprecated)]
+
+use proptest::prelude::*;
+use xorf::{BuildError, Filter, Fuse16, Fuse32, Fuse8, Xor16, Xor32, Xor64, Xor8};
+
+#[cfg(feature = "binary-fuse")]
+use xorf::{BinaryFuse16, BinaryFuse24, BinaryFuse32, BinaryFuse64, BinaryFuse8};
+
+// Key set sizes span the smallest filters, where rounding in the sizing of filters is most
+// likely to go wrong, up to filters with several segments.
+const MAX_KEYS: usize = 2_000;
+
+fn key_sets() -> impl Strategy<Value = Vec<u64>> {
+    prop_oneof![
+        prop::collection::btree_set(any::<u64>(), 0..=4),
+        prop::collection::btree_set(any::<u64>(), 0..=MAX_KEYS),
+    ]
+    .prop_map(|keys| keys.into_iter().collect())
+}
+
+fn contains_all<F: Filter<u64>>(filter: &F, keys: &[u64]) -> bool {
+    keys.iter().all(|key| filter.contains(key))
+}
+
+// Xor and Binary Fuse filters are constructed from any set of distinct keys.
+macro_rules! always_constructed {
+    ($($name:ident: $filter:ty),* $(,)?) => {$(
+        proptest! {
+            #[test]
+            fn $name(keys in key_sets()) {
+                let filter = <$filter>::try_from_iterator(keys.iter().copied());
+                prop_assert!(filter.is_ok(), "{:?}", filter.err());
+                prop_assert!(contains_all(&filter.unwrap(), &keys));
+            }
+        }
+    )*};
+}
+
+// Fuse filters fail to place small key sets, which they document with `ConstructionFailed`.
+macro_rules! sometimes_constructed {
+    ($($name:ident: $filter:ty),* $(,)?) => {$(
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(32))]
+            #[test]
+            fn $name(keys in key_sets()) {
+                match <$filter>::try_from_iterator(keys.iter().copied()) {
+                    Ok(filter) => prop_assert!(contains_all(&filter, &keys)),
+                    Err(err) => prop_assert_eq!(err, BuildError::ConstructionFailed),
+                }
+            }
+        }
+    )*};
+}
+
+always_constructed!(
+    xor8: Xor8,
+    xor16: Xor16,
+    xor32: Xor32,
+    xor64: Xor64,
+);
+
+#[cfg(feature = "binary-fuse")]
+always_constructed!(
+    bfuse8: BinaryFuse8,
+    bfuse16: BinaryFuse16,
+    bfuse24: BinaryFuse24,
+    bfuse32: BinaryFuse32,
+    bfuse64: BinaryFuse64,
+);
+
+sometimes_constructed!(
+    fuse8: Fuse8,
+    fuse16: Fuse16,
+    fuse32: Fuse32,
+);
+
+#[test]
+fn edge_sizes() {
+    // Multiplying by an odd constant is a bijection, so the keys are distinct.
+    for size in 0..=64 {
+        let keys: Vec<u64> = (0..size)
+            .map(|key: u64| key.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .collect();
+        assert!(contains_all(
+            &Xor8::try_from_iterator(keys.iter().copied()).unwrap(),
+            &keys
+        ));
+        #[cfg(feature = "binary-fuse")]
+        assert!(contains_all(
+            &BinaryFuse8::try_from_iterator(keys.iter().copied()).unwrap(),
+            &keys
+        ));
+    }
+}